@@ -0,0 +1,58 @@
+use crate::config::BACKLIGHT_FADE_TIME;
+use esp_idf_svc::hal::gpio::OutputPin;
+use esp_idf_svc::hal::ledc::config::TimerConfig;
+use esp_idf_svc::hal::ledc::{LedcChannel, LedcDriver, LedcTimer, LedcTimerDriver};
+use esp_idf_svc::hal::peripheral::Peripheral;
+use esp_idf_svc::hal::units::*;
+use std::thread;
+
+/// Brightness changes are split into this many steps
+const FADE_STEPS: u32 = 16;
+
+/// Display backlight driven by LEDC PWM, so its brightness can be changed.
+pub struct Backlight<'d> {
+    driver: LedcDriver<'d>,
+    /// Current brightness in %
+    brightness: u8,
+}
+
+impl<'d> Backlight<'d> {
+    pub fn new<C, T>(
+        channel: impl Peripheral<P = C> + 'd,
+        timer: impl Peripheral<P = T> + 'd,
+        pin: impl Peripheral<P = impl OutputPin> + 'd,
+    ) -> anyhow::Result<Self>
+    where
+        C: LedcChannel<SpeedMode = T::SpeedMode>,
+        T: LedcTimer + 'd,
+    {
+        // Above audible range, so the panel doesn't whine
+        let timer = LedcTimerDriver::new(timer, &TimerConfig::new().frequency(25.kHz().into()))?;
+        let mut driver = LedcDriver::new(channel, timer, pin)?;
+        driver.set_duty(0)?;
+        Ok(Self {
+            driver,
+            brightness: 0,
+        })
+    }
+
+    /// Gradually changes brightness to `brightness` % over `BACKLIGHT_FADE_TIME`.
+    pub fn fade_to(&mut self, brightness: u8) {
+        let from = i32::from(self.brightness);
+        let to = i32::from(brightness.min(100));
+        if from == to {
+            return;
+        }
+        for step in 1..=FADE_STEPS as i32 {
+            self.set_brightness((from + (to - from) * step / FADE_STEPS as i32) as u8);
+            thread::sleep(BACKLIGHT_FADE_TIME / FADE_STEPS);
+        }
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        // Perceived brightness isn't linear, squaring makes the steps look more even
+        let duty = self.driver.get_max_duty() * u32::from(brightness).pow(2) / 100_u32.pow(2);
+        self.driver.set_duty(duty).ok();
+    }
+}
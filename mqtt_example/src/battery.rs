@@ -1,4 +1,7 @@
-use crate::event::DeviceEvent;
+use crate::config::{
+    BATTERY_CHARGING_READ_INTERVAL, BATTERY_HYSTERESIS, BATTERY_READ_INTERVAL, BATTERY_SAMPLES,
+    BATTERY_SAMPLE_SPACING, BATTERY_SMOOTHING,
+};
 use esp_idf_svc::hal::adc::attenuation::DB_11;
 use esp_idf_svc::hal::adc::oneshot::config::{AdcChannelConfig, Calibration};
 use esp_idf_svc::hal::adc::oneshot::{AdcChannelDriver, AdcDriver};
@@ -6,10 +9,11 @@ use esp_idf_svc::hal::adc::Resolution;
 use esp_idf_svc::hal::gpio::ADCPin;
 use esp_idf_svc::hal::peripheral::Peripheral;
 use log::info;
+use quiz_core::event::DeviceEvent;
+use quiz_core::filter;
 use std::sync::mpsc;
 use std::thread;
 use std::thread::{Scope, ScopedJoinHandle};
-use std::time::Duration;
 
 mod capacity_curve {
     use log::info;
@@ -82,13 +86,18 @@ mod capacity_curve {
     /// We can still naively check for unusually high voltage and assume that battery is charging.
     const CHARGING_THRESHOLD: u16 = 4300;
 
+    #[inline]
+    pub fn is_charging(adc_reading: u16) -> bool {
+        get_battery_voltage(adc_reading) > CHARGING_THRESHOLD
+    }
+
     /// Maps `adc_reading` to battery capacity (in %).
     /// Returns `None`, if battery is charging.
     #[inline]
     pub fn get_battery_level(adc_reading: u16) -> Option<u8> {
         let voltage = get_battery_voltage(adc_reading);
         info!("[Battery reader] Voltage: {:.2}V", voltage as f32 / 1000.0);
-        if voltage > CHARGING_THRESHOLD {
+        if is_charging(adc_reading) {
             return None;
         }
         let lookup_index =
@@ -123,22 +132,44 @@ where
                 },
             )
             .unwrap();
+            let mut samples = [0; BATTERY_SAMPLES];
+            let mut voltage = filter::MovingAverage::new(BATTERY_SMOOTHING);
+            let mut hysteresis = filter::Hysteresis::new(BATTERY_HYSTERESIS);
+            let mut last_sent = None;
             loop {
-                if let Ok(reading) = adc_driver.read(&mut bat_adc_channel) {
-                    let battery_level = capacity_curve::get_battery_level(reading);
+                let mut count = 0;
+                for _ in 0..BATTERY_SAMPLES {
+                    if let Ok(reading) = adc_driver.read(&mut bat_adc_channel) {
+                        samples[count] = reading;
+                        count += 1;
+                    }
+                    thread::sleep(BATTERY_SAMPLE_SPACING);
+                }
+                if count == 0 {
+                    thread::sleep(BATTERY_READ_INTERVAL);
+                    continue;
+                }
+                let reading = filter::median(&mut samples[..count]);
+                let battery_level = if capacity_curve::is_charging(reading) {
+                    // Charging voltage would skew the average long after unplugging
+                    voltage.reset();
+                    hysteresis.reset();
+                    None
+                } else {
+                    capacity_curve::get_battery_level(voltage.update(reading))
+                        .map(|level| hysteresis.update(level))
+                };
+                if last_sent != Some(battery_level) {
+                    last_sent = Some(battery_level);
                     sender
                         .send(DeviceEvent::BatteryLevel {
                             data: battery_level,
                         })
                         .ok();
-                    match battery_level {
-                        Some(_) => {
-                            thread::sleep(Duration::from_secs(60));
-                        }
-                        None => {
-                            thread::sleep(Duration::from_secs(2));
-                        }
-                    }
+                }
+                match battery_level {
+                    Some(_) => thread::sleep(BATTERY_READ_INTERVAL),
+                    None => thread::sleep(BATTERY_CHARGING_READ_INTERVAL),
                 }
             }
         })
@@ -0,0 +1,32 @@
+use crate::config::CLOCK_TIMEZONE;
+use esp_idf_svc::sntp::EspSntp;
+use esp_idf_svc::sys::{localtime_r, time, time_t, tm, tzset};
+use log::info;
+
+/// Anything earlier means the time hasn't been synchronized yet (the clock starts at 1970)
+const SYNCHRONIZED_AFTER: time_t = 1_700_000_000;
+
+/// Starts synchronizing time over SNTP, when the clock is enabled.
+/// Synchronization stops, when the returned handle is dropped.
+pub fn start() -> anyhow::Result<Option<EspSntp<'static>>> {
+    let Some(timezone) = CLOCK_TIMEZONE else {
+        return Ok(None);
+    };
+    info!("[Clock] Synchronizing time, timezone: {timezone}");
+    std::env::set_var("TZ", timezone);
+    unsafe { tzset() };
+    Ok(Some(EspSntp::new_default()?))
+}
+
+/// Returns local hours and minutes, or `None` when the clock is disabled or not synchronized yet.
+pub fn local_time() -> Option<(u8, u8)> {
+    CLOCK_TIMEZONE?;
+    let mut now: time_t = 0;
+    unsafe { time(&mut now) };
+    if now < SYNCHRONIZED_AFTER {
+        return None;
+    }
+    let mut local = tm::default();
+    unsafe { localtime_r(&now, &mut local) };
+    Some((local.tm_hour as u8, local.tm_min as u8))
+}
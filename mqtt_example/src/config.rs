@@ -1,25 +1,84 @@
-use embedded_graphics::mono_font::ascii::FONT_10X20;
-use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::pixelcolor::Rgb565;
-use embedded_graphics::prelude::*;
-
-use embedded_text::alignment::{HorizontalAlignment, VerticalAlignment};
-use embedded_text::style::{TextBoxStyle, TextBoxStyleBuilder};
+use std::time::Duration;
 
 pub const WIFI_SSID: &str = env!("WIFI_SSID");
 pub const WIFI_PASSWORD: &str = env!("WIFI_PASSWORD");
+/// Pause between attempts to join the access point, while starting up
+pub const WIFI_RETRY_INTERVAL: Duration = Duration::from_secs(5);
 
 pub const MQTT_BROKER_URL: &str = env!("MQTT_BROKER_URL");
 pub const MQTT_USER: &str = env!("MQTT_USER");
 pub const MQTT_PASSWORD: &str = env!("MQTT_PASSWORD");
+/// mDNS service used to look up a broker in the local network, e.g. `_mqtt._tcp`.
+/// `MQTT_BROKER_URL` is used as a fallback, if not set or no broker answers.
+pub const MQTT_MDNS_SERVICE: Option<&str> = option_env!("MQTT_MDNS_SERVICE");
+pub const MQTT_MDNS_TIMEOUT: Duration = Duration::from_secs(3);
+/// Bigger messages (which arrive in chunks) are dropped, before allocating memory for them
+pub const MQTT_MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Where button presses come from: `gpio` (default), `touch` or `script`.
+pub const INPUT_SOURCE: Option<&str> = option_env!("INPUT_SOURCE");
+/// Used by `script` input source, see `ScriptedInput::parse` for the format.
+pub const INPUT_SCRIPT: &str = match option_env!("INPUT_SCRIPT") {
+    Some(script) => script,
+    None => "",
+};
+
+/// How long a button has to keep its level, before a press or release is reported.
+/// GPIO35 (ENTER) has no pull up resistor and picks up more noise, than GPIO0 (SELECT).
+pub const SELECT_STABLE_TIME: Duration = Duration::from_millis(20);
+pub const ENTER_STABLE_TIME: Duration = Duration::from_millis(50);
+pub const DEBOUNCE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+pub const TOUCH_STABLE_TIME: Duration = Duration::from_millis(30);
+pub const TOUCH_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Touch pad counts as pressed, when its reading drops below this % of the untouched one.
+pub const TOUCH_THRESHOLD_PERCENT: u32 = 80;
+/// Set to enable a rotary encoder connected to GPIO25 (A), GPIO26 (B) and GPIO27 (switch).
+pub const ROTARY_ENCODER: bool = option_env!("ROTARY_ENCODER").is_some();
+/// Pulse counter counts every edge of both encoder channels
+pub const ENCODER_COUNTS_PER_DETENT: i16 = 4;
+pub const ENCODER_SWITCH_STABLE_TIME: Duration = Duration::from_millis(20);
+
+pub const LONG_PRESS_TIME: Duration = Duration::from_millis(800);
+/// Single presses are reported with this delay, as they may still turn into a double press.
+pub const DOUBLE_PRESS_WINDOW: Duration = Duration::from_millis(250);
+
+/// How many of the last events are kept for `dump` topic
+pub const EVENT_RECORDER_CAPACITY: usize = 64;
+
+/// Periodic work of the main loop (refreshing diagnostics, turning pages) happens this often
+pub const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// How long each page of text, that doesn't fit on the screen, is shown
+pub const PAGE_INTERVAL: Duration = Duration::from_secs(3);
+pub const CONFETTI_DURATION: Duration = Duration::from_secs(4);
+/// How long the wipe between consecutive questions takes
+pub const TRANSITION_DURATION: Duration = Duration::from_millis(400);
+
+/// Backlight brightness in %, while the display is on
+pub const BACKLIGHT_BRIGHTNESS: u8 = 100;
+/// Backlight brightness in % after `BACKLIGHT_DIM_AFTER` without input or new messages
+pub const BACKLIGHT_DIM_BRIGHTNESS: u8 = 15;
+pub const BACKLIGHT_DIM_AFTER: Duration = Duration::from_secs(30);
+pub const BACKLIGHT_FADE_TIME: Duration = Duration::from_millis(250);
+/// Clockwise rotation of the screen in degrees: `0` (default, portrait), `90`, `180` or `270`.
+pub const DISPLAY_ROTATION: Option<&str> = option_env!("DISPLAY_ROTATION");
 
-pub const DISPLAY_OFFSET: (u16, u16) = (52, 40);
-pub const DISPLAY_SIZE: (u16, u16) = (135, 240);
+/// Theme used after boot: `dark` (default), `light` or `high-contrast`.
+/// Long press of SELECT switches to the next one.
+pub const THEME: Option<&str> = option_env!("THEME");
+/// POSIX TZ string, e.g. `CET-1CEST,M3.5.0,M10.5.0/3` for Poland.
+/// When set, time is synchronized over SNTP and shown in the status bar.
+pub const CLOCK_TIMEZONE: Option<&str> = option_env!("CLOCK_TIMEZONE");
+/// Name shown on the idle screen, the device id is shown when not set
+pub const DEVICE_NAME: Option<&str> = option_env!("DEVICE_NAME");
 
-pub const CHAR_STYLE: MonoTextStyle<Rgb565> = MonoTextStyle::new(&FONT_10X20, Rgb565::WHITE);
-pub const CHAR_STYLE_SELECTED: MonoTextStyle<Rgb565> =
-    MonoTextStyle::new(&FONT_10X20, Rgb565::GREEN);
-pub const TEXTBOX_STYLE: TextBoxStyle = TextBoxStyleBuilder::new()
-    .alignment(HorizontalAlignment::Left)
-    .vertical_alignment(VerticalAlignment::Middle)
-    .build();
+/// ADC readings taken for every battery measurement, their median is used
+pub const BATTERY_SAMPLES: usize = 16;
+/// Spreads samples out, so a single Wi-Fi transmission doesn't affect all of them
+pub const BATTERY_SAMPLE_SPACING: Duration = Duration::from_millis(5);
+pub const BATTERY_READ_INTERVAL: Duration = Duration::from_secs(10);
+/// Charging is checked more often, so the status bar reacts to plugging the cable in
+pub const BATTERY_CHARGING_READ_INTERVAL: Duration = Duration::from_secs(2);
+/// Weight of a new measurement in the moving average of the voltage, `0.0..=1.0`
+pub const BATTERY_SMOOTHING: f32 = 0.2;
+/// Change of the battery level (in %), needed to report a new one
+pub const BATTERY_HYSTERESIS: u8 = 3;
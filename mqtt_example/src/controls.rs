@@ -1,110 +1,57 @@
-use crate::event::DeviceEvent;
-use esp_idf_svc::hal::delay;
-use esp_idf_svc::hal::gpio::{Input, InputPin, InterruptType, PinDriver};
-use esp_idf_svc::hal::task::notification::Notification;
-use std::num::NonZeroU32;
+use crate::config::{DOUBLE_PRESS_WINDOW, LONG_PRESS_TIME};
+use crate::input::InputSource;
+use quiz_core::event::DeviceEvent;
+use quiz_core::gesture::{Button, Gesture, GestureRecognizer};
 use std::sync::mpsc;
 use std::thread;
 use std::thread::{Scope, ScopedJoinHandle};
-use std::time::Duration;
 
-pub struct Controls<'controls, SELECT, ENTER>
+/// Spawns a thread that reads key presses from input created by `make_input`,
+/// transforms them into `DeviceEvent`s and sends to mpsc channel.
+///
+/// Input is created on the spawned thread, as e.g. GPIO interrupts
+/// have to notify the same task, that waits for them.
+pub fn spawn_thread<'scope, F>(
+    scope: &'scope Scope<'scope, '_>,
+    make_input: F,
+    sender: mpsc::Sender<DeviceEvent>,
+) -> Result<ScopedJoinHandle<'scope, ()>, std::io::Error>
 where
-    SELECT: InputPin,
-    ENTER: InputPin,
+    F: FnOnce() -> anyhow::Result<Box<dyn InputSource>> + Send + 'scope,
 {
-    btn_select: PinDriver<'controls, SELECT, Input>,
-    btn_enter: PinDriver<'controls, ENTER, Input>,
-
-    selection: u8,
-}
-
-impl<'controls, SELECT: InputPin, ENTER: InputPin> Controls<'controls, SELECT, ENTER> {
-    pub fn new(pin_select: SELECT, pin_enter: ENTER) -> anyhow::Result<Self> {
-        let mut btn_select = PinDriver::input(pin_select)?;
-        let mut btn_enter = PinDriver::input(pin_enter)?;
-
-        btn_select.set_interrupt_type(InterruptType::NegEdge)?;
-        btn_enter.set_interrupt_type(InterruptType::NegEdge)?;
-
-        Ok(Self {
-            btn_select,
-            btn_enter,
-            selection: 0,
-        })
-    }
-
-    /// Spawns a thread that reads key presses,
-    /// transforms them into `DeviceEvent`s and sends to mpsc channel.
-    pub fn spawn_thread<'scope>(
-        mut self,
-        scope: &'scope Scope<'scope, '_>,
-        sender: mpsc::Sender<DeviceEvent>,
-    ) -> Result<ScopedJoinHandle<'scope, ()>, std::io::Error>
-    where
-        'controls: 'scope,
-    {
-        thread::Builder::new()
-            .stack_size(8192)
-            .spawn_scoped(scope, move || {
-                let notification = Notification::new();
-                let notifier_select = notification.notifier();
-                let notifier_enter = notification.notifier();
-
-                // Usage of interrupts is currently unsafe.
-                // We have to make sure, notifiers don't outlive the current task (thread).
-                //
-                // We are using notifiers instead of mpsc directly,
-                // as the callback closure inside `subscribe`
-                // is moved into ISR (Interrupt Service Routine),
-                // and the list of allowed APIs there is heavily restricted.
-                unsafe {
-                    self.btn_select
-                        .subscribe(move || {
-                            notifier_select.notify_and_yield(NonZeroU32::new(1).unwrap());
-                        })
-                        .unwrap();
-                    self.btn_enter
-                        .subscribe(move || {
-                            notifier_enter.notify_and_yield(NonZeroU32::new(2).unwrap());
-                        })
-                        .unwrap();
+    thread::Builder::new()
+        .stack_size(8192)
+        .spawn_scoped(scope, move || {
+            let mut input = make_input().unwrap();
+            let mut gestures = GestureRecognizer::new(LONG_PRESS_TIME, DOUBLE_PRESS_WINDOW);
+            loop {
+                let now = input.now();
+                while let Some(gesture) = gestures.poll(now) {
+                    send_gesture(gesture, &sender);
                 }
-                loop {
-                    // We have to re-enable interrupts each time,
-                    // as they are automatically disabled when interrupt happens.
-                    self.enable_interrupts().unwrap();
-                    let btn_num = notification.wait(delay::BLOCK).unwrap().get();
-                    match btn_num {
-                        1 => {
-                            self.selection += 1;
-                            if self.selection > 3 {
-                                self.selection = 0;
-                            }
-                            sender
-                                .send(DeviceEvent::Select {
-                                    data: self.selection,
-                                })
-                                .unwrap();
-                        }
-                        2 => {
-                            sender
-                                .send(DeviceEvent::Enter {
-                                    data: self.selection,
-                                })
-                                .unwrap();
-                        }
-                        _ => {}
+                // Wake up either on the next edge, or when a pending gesture times out
+                let timeout = gestures
+                    .next_deadline()
+                    .map(|deadline| deadline.saturating_sub(now));
+                if let Some(edge) = input.next_edge(timeout) {
+                    if let Some(gesture) = gestures.on_edge(edge.button, edge.edge, edge.at) {
+                        send_gesture(gesture, &sender);
                     }
-                    // GPIO35 has no pull up resistor, this helps not to send multiple events
-                    thread::sleep(Duration::from_millis(500))
                 }
-            })
-    }
+            }
+        })
+}
 
-    fn enable_interrupts(&mut self) -> anyhow::Result<()> {
-        self.btn_select.enable_interrupt()?;
-        self.btn_enter.enable_interrupt()?;
-        Ok(())
-    }
+/// Maps gestures to intents, the quiz acts on.
+fn send_gesture(gesture: Gesture, sender: &mpsc::Sender<DeviceEvent>) {
+    let event = match gesture {
+        Gesture::Press(Button::Select) => DeviceEvent::Next,
+        // Double press of SELECT moves the selection backwards
+        Gesture::DoublePress(Button::Select) => DeviceEvent::Previous,
+        Gesture::Press(Button::Enter) => DeviceEvent::Confirm,
+        Gesture::DoublePress(button) => DeviceEvent::DoublePress { data: button },
+        Gesture::LongPress(button) => DeviceEvent::LongPress { data: button },
+        Gesture::Chord => DeviceEvent::Chord,
+    };
+    sender.send(event).unwrap();
 }
@@ -0,0 +1,17 @@
+use crate::wifi;
+
+use esp_idf_svc::sys::{esp_get_free_heap_size, esp_timer_get_time};
+use esp_idf_svc::wifi::{BlockingWifi, EspWifi};
+pub use quiz_core::diagnostics::{MqttState, NetworkDiagnostics};
+use std::time::Duration;
+
+/// Reads current Wi-Fi and system stats into `diagnostics`.
+pub fn refresh(diagnostics: &mut NetworkDiagnostics, wifi: &BlockingWifi<EspWifi>) {
+    diagnostics.rssi = wifi::get_rssi();
+    let ip_info = wifi.wifi().sta_netif().get_ip_info().ok();
+    diagnostics.ip = ip_info.map(|ip_info| ip_info.ip);
+    diagnostics.gateway = ip_info.map(|ip_info| ip_info.subnet.gateway);
+    // Both are safe to call from any task
+    diagnostics.uptime = Duration::from_micros(unsafe { esp_timer_get_time() } as u64);
+    diagnostics.free_heap = unsafe { esp_get_free_heap_size() };
+}
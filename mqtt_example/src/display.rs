@@ -1,21 +1,27 @@
-use crate::config::{CHAR_STYLE, CHAR_STYLE_SELECTED, DISPLAY_OFFSET, DISPLAY_SIZE, TEXTBOX_STYLE};
-use embedded_graphics::pixelcolor::Rgb565;
+use crate::backlight::Backlight;
+use crate::config::{BACKLIGHT_BRIGHTNESS, BACKLIGHT_DIM_BRIGHTNESS, DISPLAY_ROTATION};
+use crate::diagnostics::NetworkDiagnostics;
+use crate::display_profile::{Model, PROFILE};
+
 use embedded_graphics::prelude::*;
-use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle, StyledDrawable};
-use embedded_graphics::text::Text;
-use embedded_hal::spi::MODE_3;
-use embedded_text::alignment::{HorizontalAlignment, VerticalAlignment};
-use embedded_text::style::TextBoxStyleBuilder;
-use embedded_text::TextBox;
+use embedded_graphics::primitives::Rectangle;
 use esp_idf_svc::hal::delay::Ets;
 use esp_idf_svc::hal::gpio::{AnyIOPin, Output, OutputPin, PinDriver};
 use esp_idf_svc::hal::peripheral::Peripheral;
 use esp_idf_svc::hal::spi::{SpiAnyPins, SpiConfig, SpiDeviceDriver, SpiDriver, SpiDriverConfig};
 use esp_idf_svc::hal::units::*;
+use log::warn;
 use mipidsi::interface::SpiInterface;
-use mipidsi::models::ST7789;
-use mipidsi::options::ColorInversion;
+use mipidsi::options::{Orientation, Rotation};
 use mipidsi::{Builder, Display};
+use qrcodegen::QrCode;
+use quiz_core::animation::Animation;
+use quiz_core::framebuffer::BufferedPanel;
+use quiz_core::markup::Markup;
+use quiz_core::quiz::Progress;
+use quiz_core::renderer::{QuizRenderer, Renderer};
+use quiz_core::status_bar::StatusBarState;
+use quiz_core::theme::Theme;
 
 type DisplaySpiInterface<'spi, DC> =
     SpiInterface<'spi, SpiDeviceDriver<'spi, SpiDriver<'spi>>, PinDriver<'spi, DC, Output>>;
@@ -23,34 +29,35 @@ type DisplaySpiInterface<'spi, DC> =
 type SpiDisplay<'display, DC, MODEL, RST> =
     Display<DisplaySpiInterface<'display, DC>, MODEL, PinDriver<'display, RST, Output>>;
 
-pub struct QuizDisplay<'display, DC, RST, BL>
+pub struct QuizDisplay<'display, DC, RST>
 where
     DC: OutputPin,
     RST: OutputPin,
-    BL: OutputPin,
 {
-    display: SpiDisplay<'display, DC, ST7789, RST>,
-    backlight: PinDriver<'display, BL, Output>,
+    backlight: Backlight<'display>,
+    renderer: Renderer<BufferedPanel<SpiDisplay<'display, DC, Model, RST>>>,
+    awake: bool,
+    dimmed: bool,
 }
 
+/// Drawing (including `clear`) only changes the off-screen framebuffer,
+/// call `flush` to show the changes on the panel.
+/// `clear` leaves the status bar alone, it's only redrawn where its icons change.
 pub trait DisplayControls {
     fn clear(&mut self);
+    fn flush(&mut self);
     fn on(&mut self);
     fn off(&mut self);
+    /// Lowers the backlight, while the display is on. Remembered across `off` and `on`.
+    fn set_dimmed(&mut self, dimmed: bool);
+    /// Limits drawing to `area`, until reset with `None`.
+    fn set_clip(&mut self, area: Option<Rectangle>);
 }
 
-pub trait QuizRenderer {
-    fn draw_question(&mut self, question: &str);
-    fn draw_options(&mut self, options: &[String], selected: u8);
-    fn draw_text(&mut self, text: &str);
-    fn draw_battery_level(&mut self, battery_level: Option<u8>);
-}
-
-impl<'display, DC, RST, BL> QuizDisplay<'display, DC, RST, BL>
+impl<'display, DC, RST> QuizDisplay<'display, DC, RST>
 where
     DC: OutputPin,
     RST: OutputPin,
-    BL: OutputPin,
 {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -60,23 +67,35 @@ where
         cs: impl Peripheral<P = impl OutputPin> + 'display,
         dc: impl Peripheral<P = DC> + 'display,
         rst: impl Peripheral<P = RST> + 'display,
-        bl: impl Peripheral<P = BL> + 'display,
+        backlight: Backlight<'display>,
         buffer: &'display mut [u8],
     ) -> Self {
-        let backlight: PinDriver<'display, BL, Output> = PinDriver::output(bl).unwrap();
-
         let spi_interface = Self::configure_spi(spi, sclk, sdo, cs, dc, buffer);
 
         let mut delay = Ets;
-        let display = Builder::new(ST7789, spi_interface)
-            .invert_colors(ColorInversion::Inverted)
+        let display = Builder::new(PROFILE.model, spi_interface)
+            .invert_colors(PROFILE.inversion)
+            .color_order(PROFILE.color_order)
             .reset_pin(PinDriver::output(rst).unwrap())
-            .display_offset(DISPLAY_OFFSET.0, DISPLAY_OFFSET.1)
-            .display_size(DISPLAY_SIZE.0, DISPLAY_SIZE.1)
+            .display_offset(PROFILE.offset.0, PROFILE.offset.1)
+            .display_size(PROFILE.size.0, PROFILE.size.1)
+            .orientation(Orientation::new().rotate(rotation()))
             .init(&mut delay)
             .expect("Failed to init display");
 
-        Self { display, backlight }
+        // Size of a rotated display already has width and height swapped
+        let panel = BufferedPanel::new(display);
+        if !panel.is_buffered() {
+            warn!("[Display] Not enough memory for a framebuffer, drawing straight to the panel");
+        }
+        let renderer = Renderer::new(panel);
+
+        Self {
+            backlight,
+            renderer,
+            awake: true,
+            dimmed: false,
+        }
     }
 
     fn configure_spi(
@@ -87,7 +106,9 @@ where
         dc: impl Peripheral<P = DC> + 'display,
         buffer: &'display mut [u8],
     ) -> DisplaySpiInterface<'display, DC> {
-        let config = SpiConfig::new().baudrate(26.MHz().into()).data_mode(MODE_3);
+        let config = SpiConfig::new()
+            .baudrate(PROFILE.spi_baudrate_mhz.MHz().into())
+            .data_mode(PROFILE.spi_mode);
         let spi_device = SpiDeviceDriver::new_single(
             spi,
             sclk,
@@ -101,137 +122,149 @@ where
 
         SpiInterface::new(spi_device, PinDriver::output(dc).unwrap(), buffer)
     }
+
+    fn brightness(&self) -> u8 {
+        if self.dimmed {
+            BACKLIGHT_DIM_BRIGHTNESS
+        } else {
+            BACKLIGHT_BRIGHTNESS
+        }
+    }
 }
 
-impl<DC, RST, BL> DisplayControls for QuizDisplay<'_, DC, RST, BL>
+impl<DC, RST> DisplayControls for QuizDisplay<'_, DC, RST>
 where
     DC: OutputPin,
     RST: OutputPin,
-    BL: OutputPin,
 {
     fn clear(&mut self) {
-        self.display.clear(Rgb565::BLACK).ok();
+        self.renderer.clear();
+    }
+
+    fn flush(&mut self) {
+        self.renderer.target_mut().flush().ok();
     }
 
     fn on(&mut self) {
-        self.backlight.set_high().ok();
+        // Screens are redrawn periodically, waking the panel every time would add delays
+        if self.awake {
+            return;
+        }
+        self.awake = true;
         let mut delay = Ets;
-        self.display.wake(&mut delay).unwrap();
+        self.renderer
+            .target_mut()
+            .panel_mut()
+            .wake(&mut delay)
+            .unwrap();
+        self.backlight.fade_to(self.brightness());
     }
 
     fn off(&mut self) {
-        self.backlight.set_low().ok();
+        if !self.awake {
+            return;
+        }
+        self.awake = false;
+        self.backlight.fade_to(0);
         let mut delay = Ets;
-        self.display.sleep(&mut delay).unwrap();
+        self.renderer
+            .target_mut()
+            .panel_mut()
+            .sleep(&mut delay)
+            .unwrap();
     }
-}
 
-fn draw_selection_arrow<D>(display: &mut D, y_offset: i32, selected: bool)
-where
-    D: DrawTarget<Color = Rgb565>,
-{
-    if selected {
-        Text::new(">", Point::new(0, y_offset + 25), CHAR_STYLE_SELECTED)
-            .draw(display)
-            .ok();
-    } else {
-        // Draw a black rectangle where the selection arrow is,
-        // so we don't have to re-render the whole screen.
-        Rectangle::new(Point::new(0, y_offset + 6), Size::new(10, 20))
-            .draw_styled(&PrimitiveStyle::with_fill(Rgb565::BLACK), display)
-            .ok();
+    fn set_dimmed(&mut self, dimmed: bool) {
+        if self.dimmed == dimmed {
+            return;
+        }
+        self.dimmed = dimmed;
+        if self.awake {
+            self.backlight.fade_to(self.brightness());
+        }
+    }
+
+    fn set_clip(&mut self, area: Option<Rectangle>) {
+        self.renderer.target_mut().set_clip(area);
     }
 }
 
-fn draw_option<D>(display: &mut D, y_offset: i32, selected: bool, option: &str)
-where
-    D: DrawTarget<Color = Rgb565>,
-{
-    Line::new(
-        Point::new(0, y_offset),
-        Point::new(DISPLAY_SIZE.0.into(), y_offset),
-    )
-    .draw_styled(&PrimitiveStyle::with_stroke(Rgb565::WHITE, 1), display)
-    .ok();
-    TextBox::with_textbox_style(
-        option,
-        Rectangle::new(
-            Point::new(16, y_offset),
-            Size::new((DISPLAY_SIZE.0 - 16).into(), 40),
-        ),
-        if selected {
-            CHAR_STYLE_SELECTED
-        } else {
-            CHAR_STYLE
-        },
-        TEXTBOX_STYLE,
-    )
-    .draw(display)
-    .ok();
+/// Reads `DISPLAY_ROTATION`, falling back to the native orientation.
+fn rotation() -> Rotation {
+    match DISPLAY_ROTATION {
+        Some("90") => Rotation::Deg90,
+        Some("180") => Rotation::Deg180,
+        Some("270") => Rotation::Deg270,
+        _ => Rotation::Deg0,
+    }
 }
 
-impl<DC, RST, BL> QuizRenderer for QuizDisplay<'_, DC, RST, BL>
+impl<DC, RST> QuizRenderer for QuizDisplay<'_, DC, RST>
 where
     DC: OutputPin,
     RST: OutputPin,
-    BL: OutputPin,
 {
-    fn draw_question(&mut self, question: &str) {
-        TextBox::with_textbox_style(
-            question,
-            Rectangle::new(
-                Point::new(0, 20),
-                Size::new(DISPLAY_SIZE.0.into(), (DISPLAY_SIZE.1 - 160 - 20).into()),
-            ),
-            CHAR_STYLE,
-            TEXTBOX_STYLE,
-        )
-        .draw(&mut self.display)
-        .ok();
+    fn draw_question(&mut self, question: &str, progress: Option<&Progress>, page: usize) {
+        self.renderer.draw_question(question, progress, page);
     }
 
-    fn draw_options(&mut self, options: &[String], selection: u8) {
-        for (idx, option) in options.iter().enumerate() {
-            let selected = idx as u8 == selection;
-            let y_offset = 40 * idx as i32 + DISPLAY_SIZE.1 as i32 - 160;
-            draw_selection_arrow(&mut self.display, y_offset, selected);
-            draw_option(&mut self.display, y_offset, selected, option);
-        }
+    fn draw_options(&mut self, options: &[String], selected: usize, page: usize) {
+        self.renderer.draw_options(options, selected, page);
     }
 
-    fn draw_text(&mut self, text: &str) {
-        TextBox::with_textbox_style(
-            text,
-            Rectangle::new(
-                Point::new(0, 20),
-                Size::new(DISPLAY_SIZE.0.into(), (DISPLAY_SIZE.1 - 20).into()),
-            ),
-            CHAR_STYLE,
-            TEXTBOX_STYLE,
-        )
-        .draw(&mut self.display)
-        .ok();
-    }
-
-    fn draw_battery_level(&mut self, battery_level: Option<u8>) {
-        let bounding_box = Rectangle::new(Point::zero(), Size::new(DISPLAY_SIZE.0.into(), 20));
-        bounding_box
-            .draw_styled(&PrimitiveStyle::with_fill(Rgb565::BLACK), &mut self.display)
-            .ok();
-        let text = match battery_level {
-            Some(level) => format!("bat: {:>3}%", level),
-            None => String::from("charging..."),
-        };
-        TextBox::with_textbox_style(
-            &text,
-            bounding_box,
-            CHAR_STYLE,
-            TextBoxStyleBuilder::new()
-                .alignment(HorizontalAlignment::Right)
-                .vertical_alignment(VerticalAlignment::Top)
-                .build(),
-        )
-        .draw(&mut self.display)
-        .ok();
+    fn draw_result(
+        &mut self,
+        options: &[String],
+        chosen: usize,
+        correct: usize,
+        points: i32,
+        page: usize,
+    ) {
+        self.renderer
+            .draw_result(options, chosen, correct, points, page);
+    }
+
+    fn draw_text(&mut self, text: &str, page: usize) {
+        self.renderer.draw_text(text, page);
+    }
+
+    fn draw_markup(&mut self, markup: &Markup, page: usize) {
+        self.renderer.draw_markup(markup, page);
+    }
+
+    fn draw_qr_code(&mut self, code: &QrCode) {
+        self.renderer.draw_qr_code(code);
+    }
+
+    fn draw_image(&mut self, data: &[u8]) {
+        self.renderer.draw_image(data);
+    }
+
+    fn draw_idle(&mut self, name: &str, time: Option<(u8, u8)>) {
+        self.renderer.draw_idle(name, time);
+    }
+
+    fn draw_animation(&mut self, animation: &dyn Animation) {
+        self.renderer.draw_animation(animation);
+    }
+
+    fn content_area(&self) -> Rectangle {
+        self.renderer.content_area()
+    }
+
+    fn draw_status_bar(&mut self, status: &StatusBarState) {
+        self.renderer.draw_status_bar(status);
+    }
+
+    fn draw_diagnostics(&mut self, diagnostics: &NetworkDiagnostics) {
+        self.renderer.draw_diagnostics(diagnostics);
+    }
+
+    fn set_theme(&mut self, theme: &'static Theme) {
+        self.renderer.set_theme(theme);
+    }
+
+    fn page_count(&self) -> usize {
+        self.renderer.page_count()
     }
 }
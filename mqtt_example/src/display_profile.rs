@@ -0,0 +1,90 @@
+use embedded_hal::spi::Mode;
+use mipidsi::options::{ColorInversion, ColorOrder};
+
+/// Everything, that differs between display panels (and the way they are wired) on our boards.
+///
+/// The panel is picked with a cargo feature, `--all-features` builds use the first one listed:
+/// - `display-ili9341`: 2.4"/2.8" ILI9341, 240x320
+/// - `display-st7735`: 1.8" ST7735S, 128x160
+/// - `display-st7789-240x240`: 1.3" ST7789, 240x240
+/// - none: 1.14" ST7789 on TTGO T-Display, 135x240
+///
+/// The framebuffer takes 2 bytes per pixel, so the ILI9341 one (~150 KB) needs PSRAM.
+/// Without it, drawing falls back to going straight to the panel, see `BufferedPanel`.
+pub struct DisplayProfile<M> {
+    pub model: M,
+    /// In the panel's native, portrait orientation
+    pub size: (u16, u16),
+    /// Position of the visible area in the controller's memory
+    pub offset: (u16, u16),
+    pub inversion: ColorInversion,
+    pub color_order: ColorOrder,
+    pub spi_baudrate_mhz: u32,
+    pub spi_mode: Mode,
+}
+
+#[cfg(feature = "display-ili9341")]
+pub type Model = mipidsi::models::ILI9341Rgb565;
+#[cfg(feature = "display-ili9341")]
+pub const PROFILE: DisplayProfile<Model> = DisplayProfile {
+    model: mipidsi::models::ILI9341Rgb565,
+    size: (240, 320),
+    offset: (0, 0),
+    inversion: ColorInversion::Normal,
+    color_order: ColorOrder::Bgr,
+    spi_baudrate_mhz: 40,
+    spi_mode: embedded_hal::spi::MODE_0,
+};
+
+#[cfg(all(feature = "display-st7735", not(feature = "display-ili9341")))]
+pub type Model = mipidsi::models::ST7735s;
+#[cfg(all(feature = "display-st7735", not(feature = "display-ili9341")))]
+pub const PROFILE: DisplayProfile<Model> = DisplayProfile {
+    model: mipidsi::models::ST7735s,
+    size: (128, 160),
+    offset: (0, 0),
+    inversion: ColorInversion::Normal,
+    color_order: ColorOrder::Bgr,
+    spi_baudrate_mhz: 15,
+    spi_mode: embedded_hal::spi::MODE_0,
+};
+
+#[cfg(all(
+    feature = "display-st7789-240x240",
+    not(any(feature = "display-ili9341", feature = "display-st7735"))
+))]
+pub type Model = mipidsi::models::ST7789;
+#[cfg(all(
+    feature = "display-st7789-240x240",
+    not(any(feature = "display-ili9341", feature = "display-st7735"))
+))]
+pub const PROFILE: DisplayProfile<Model> = DisplayProfile {
+    model: mipidsi::models::ST7789,
+    size: (240, 240),
+    offset: (0, 0),
+    inversion: ColorInversion::Inverted,
+    color_order: ColorOrder::Rgb,
+    spi_baudrate_mhz: 40,
+    spi_mode: embedded_hal::spi::MODE_3,
+};
+
+#[cfg(not(any(
+    feature = "display-ili9341",
+    feature = "display-st7735",
+    feature = "display-st7789-240x240"
+)))]
+pub type Model = mipidsi::models::ST7789;
+#[cfg(not(any(
+    feature = "display-ili9341",
+    feature = "display-st7735",
+    feature = "display-st7789-240x240"
+)))]
+pub const PROFILE: DisplayProfile<Model> = DisplayProfile {
+    model: mipidsi::models::ST7789,
+    size: (135, 240),
+    offset: (52, 40),
+    inversion: ColorInversion::Inverted,
+    color_order: ColorOrder::Rgb,
+    spi_baudrate_mhz: 26,
+    spi_mode: embedded_hal::spi::MODE_3,
+};
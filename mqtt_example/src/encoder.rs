@@ -0,0 +1,112 @@
+use crate::config::{
+    DEBOUNCE_POLL_INTERVAL, ENCODER_COUNTS_PER_DETENT, ENCODER_SWITCH_STABLE_TIME,
+};
+use esp_idf_svc::hal::gpio::{AnyInputPin, IOPin, InputPin, PinDriver, Pull};
+use esp_idf_svc::hal::pcnt::*;
+use esp_idf_svc::hal::peripheral::Peripheral;
+use log::info;
+use quiz_core::debounce::{Debouncer, Edge};
+use quiz_core::event::DeviceEvent;
+use quiz_core::quadrature;
+use std::sync::mpsc;
+use std::thread;
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::Instant;
+
+/// Pulse counter resets to 0, whenever it reaches one of the limits
+const COUNTER_LIMIT: i16 = 1000;
+
+/// Spawns a thread, that reads a rotary encoder through PCNT (pulse counter) peripheral,
+/// turns detents into `Next`/`Previous` events, push switch presses into `Confirm`
+/// and sends them to mpsc channel, alongside the ones from `controls`.
+pub fn spawn_thread<'scope>(
+    scope: &'scope Scope<'scope, '_>,
+    pcnt: impl Peripheral<P = impl Pcnt> + 'scope + Send,
+    pin_a: impl Peripheral<P = impl InputPin> + 'scope + Send,
+    pin_b: impl Peripheral<P = impl InputPin> + 'scope + Send,
+    pin_switch: impl Peripheral<P = impl IOPin> + 'scope + Send,
+    sender: mpsc::Sender<DeviceEvent>,
+) -> Result<ScopedJoinHandle<'scope, ()>, std::io::Error> {
+    thread::Builder::new()
+        .stack_size(8192)
+        .spawn_scoped(scope, move || {
+            info!("[Encoder] Starting...");
+            let counter = configure_counter(pcnt, pin_a, pin_b).unwrap();
+            let mut switch = PinDriver::input(pin_switch).unwrap();
+            // Push switch shorts the pin to ground and has no external pull up
+            switch.set_pull(Pull::Up).unwrap();
+
+            let mut decoder =
+                quadrature::DetentDecoder::new(ENCODER_COUNTS_PER_DETENT, COUNTER_LIMIT);
+            let mut debounce_switch = Debouncer::new(ENCODER_SWITCH_STABLE_TIME);
+            let start = Instant::now();
+            loop {
+                if let Ok(count) = counter.get_counter_value() {
+                    let detents = decoder.update(count);
+                    for _ in 0..detents.unsigned_abs() {
+                        let event = if detents > 0 {
+                            DeviceEvent::Next
+                        } else {
+                            DeviceEvent::Previous
+                        };
+                        sender.send(event).ok();
+                    }
+                }
+                if let Some(Edge::Pressed) =
+                    debounce_switch.update(switch.is_low(), start.elapsed())
+                {
+                    sender.send(DeviceEvent::Confirm).ok();
+                }
+                thread::sleep(DEBOUNCE_POLL_INTERVAL);
+            }
+        })
+}
+
+/// Configures PCNT to decode both encoder channels in 4x quadrature mode.
+fn configure_counter<'d>(
+    pcnt: impl Peripheral<P = impl Pcnt> + 'd,
+    pin_a: impl Peripheral<P = impl InputPin> + 'd,
+    pin_b: impl Peripheral<P = impl InputPin> + 'd,
+) -> anyhow::Result<PcntDriver<'d>> {
+    let mut counter = PcntDriver::new(
+        pcnt,
+        Some(pin_a),
+        Some(pin_b),
+        Option::<AnyInputPin>::None,
+        Option::<AnyInputPin>::None,
+    )?;
+    // Each channel counts edges of one pin, the level of the other one decides the direction
+    counter.channel_config(
+        PcntChannel::Channel0,
+        PinIndex::Pin0,
+        PinIndex::Pin1,
+        &PcntChannelConfig {
+            lctrl_mode: PcntControlMode::Reverse,
+            hctrl_mode: PcntControlMode::Keep,
+            pos_mode: PcntCountMode::Decrement,
+            neg_mode: PcntCountMode::Increment,
+            counter_h_lim: COUNTER_LIMIT,
+            counter_l_lim: -COUNTER_LIMIT,
+        },
+    )?;
+    counter.channel_config(
+        PcntChannel::Channel1,
+        PinIndex::Pin1,
+        PinIndex::Pin0,
+        &PcntChannelConfig {
+            lctrl_mode: PcntControlMode::Reverse,
+            hctrl_mode: PcntControlMode::Keep,
+            pos_mode: PcntCountMode::Increment,
+            neg_mode: PcntCountMode::Decrement,
+            counter_h_lim: COUNTER_LIMIT,
+            counter_l_lim: -COUNTER_LIMIT,
+        },
+    )?;
+    // Ignore contact bounces shorter than ~13 µs (value is in 80 MHz APB clock cycles, max 1023)
+    counter.set_filter_value(1023)?;
+    counter.filter_enable()?;
+    counter.counter_pause()?;
+    counter.counter_clear()?;
+    counter.counter_resume()?;
+    Ok(counter)
+}
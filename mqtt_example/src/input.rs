@@ -0,0 +1,6 @@
+mod gpio;
+mod touch;
+
+pub use gpio::GpioInput;
+pub use quiz_core::input::{ButtonEdge, InputSource, ScriptedInput};
+pub use touch::TouchInput;
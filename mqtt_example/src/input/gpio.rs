@@ -0,0 +1,138 @@
+use super::{ButtonEdge, InputSource};
+use crate::config::{DEBOUNCE_POLL_INTERVAL, ENTER_STABLE_TIME, SELECT_STABLE_TIME};
+use esp_idf_svc::hal::delay;
+use esp_idf_svc::hal::delay::TickType;
+use esp_idf_svc::hal::gpio::{Input, InputPin, InterruptType, PinDriver};
+use esp_idf_svc::hal::task::notification::Notification;
+use quiz_core::debounce::Debouncer;
+use quiz_core::gesture::Button;
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Buttons connected to GPIO pins, read using interrupts.
+pub struct GpioInput<'input, SELECT, ENTER>
+where
+    SELECT: InputPin,
+    ENTER: InputPin,
+{
+    // Pins are declared (and dropped) before `notification`,
+    // so interrupt handlers never notify a task, that's gone.
+    btn_select: PinDriver<'input, SELECT, Input>,
+    btn_enter: PinDriver<'input, ENTER, Input>,
+    notification: Notification,
+    debounce_select: Debouncer,
+    debounce_enter: Debouncer,
+    pending: VecDeque<ButtonEdge>,
+    start: Instant,
+}
+
+impl<'input, SELECT: InputPin, ENTER: InputPin> GpioInput<'input, SELECT, ENTER> {
+    /// Has to be called on the thread, that reads the input,
+    /// as interrupts notify the task, that subscribed to them.
+    pub fn new(pin_select: SELECT, pin_enter: ENTER) -> anyhow::Result<Self> {
+        let mut btn_select = PinDriver::input(pin_select)?;
+        let mut btn_enter = PinDriver::input(pin_enter)?;
+
+        // Both edges are needed, so debouncers can see when the level settles
+        btn_select.set_interrupt_type(InterruptType::AnyEdge)?;
+        btn_enter.set_interrupt_type(InterruptType::AnyEdge)?;
+
+        let notification = Notification::new();
+        let notifier_select = notification.notifier();
+        let notifier_enter = notification.notifier();
+
+        // Usage of interrupts is currently unsafe.
+        // We have to make sure, notifiers don't outlive the current task (thread).
+        //
+        // We are using notifiers instead of mpsc directly,
+        // as the callback closure inside `subscribe`
+        // is moved into ISR (Interrupt Service Routine),
+        // and the list of allowed APIs there is heavily restricted.
+        unsafe {
+            btn_select.subscribe(move || {
+                notifier_select.notify_and_yield(NonZeroU32::new(1).unwrap());
+            })?;
+            btn_enter.subscribe(move || {
+                notifier_enter.notify_and_yield(NonZeroU32::new(2).unwrap());
+            })?;
+        }
+
+        Ok(Self {
+            btn_select,
+            btn_enter,
+            notification,
+            debounce_select: Debouncer::new(SELECT_STABLE_TIME),
+            debounce_enter: Debouncer::new(ENTER_STABLE_TIME),
+            pending: VecDeque::new(),
+            start: Instant::now(),
+        })
+    }
+
+    fn is_settling(&self) -> bool {
+        self.debounce_select.is_settling() || self.debounce_enter.is_settling()
+    }
+
+    /// Feeds current pin levels to debouncers and queues debounced edges.
+    fn sample(&mut self) {
+        let now = self.now();
+        if let Some(edge) = self.debounce_select.update(self.btn_select.is_low(), now) {
+            self.pending.push_back(ButtonEdge {
+                button: Button::Select,
+                edge,
+                at: now,
+            });
+        }
+        if let Some(edge) = self.debounce_enter.update(self.btn_enter.is_low(), now) {
+            self.pending.push_back(ButtonEdge {
+                button: Button::Enter,
+                edge,
+                at: now,
+            });
+        }
+    }
+
+    fn enable_interrupts(&mut self) -> anyhow::Result<()> {
+        self.btn_select.enable_interrupt()?;
+        self.btn_enter.enable_interrupt()?;
+        Ok(())
+    }
+}
+
+impl<SELECT: InputPin, ENTER: InputPin> InputSource for GpioInput<'_, SELECT, ENTER> {
+    fn next_edge(&mut self, timeout: Option<Duration>) -> Option<ButtonEdge> {
+        let deadline = timeout.map(|timeout| self.now() + timeout);
+        loop {
+            if let Some(edge) = self.pending.pop_front() {
+                return Some(edge);
+            }
+            let now = self.now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return None;
+            }
+            if self.is_settling() {
+                // Levels are sampled every `DEBOUNCE_POLL_INTERVAL`, until they are stable
+                thread::sleep(DEBOUNCE_POLL_INTERVAL);
+            } else {
+                // We have to re-enable interrupts each time,
+                // as they are automatically disabled when interrupt happens.
+                self.enable_interrupts().unwrap();
+                // Catch edges, that happened while interrupts were disabled
+                self.sample();
+                if self.pending.is_empty() && !self.is_settling() {
+                    let ticks = match deadline {
+                        Some(deadline) => TickType::from(deadline - now).ticks(),
+                        None => delay::BLOCK,
+                    };
+                    self.notification.wait(ticks);
+                }
+            }
+            self.sample();
+        }
+    }
+
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
@@ -0,0 +1,106 @@
+use super::{ButtonEdge, InputSource};
+use crate::config::{TOUCH_POLL_INTERVAL, TOUCH_STABLE_TIME, TOUCH_THRESHOLD_PERCENT};
+use esp_idf_svc::hal::gpio::{Gpio32, Gpio33};
+use esp_idf_svc::sys::*;
+use quiz_core::debounce::Debouncer;
+use quiz_core::gesture::Button;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// GPIO32
+const SELECT_PAD: touch_pad_t = touch_pad_t_TOUCH_PAD_NUM9;
+/// GPIO33
+const ENTER_PAD: touch_pad_t = touch_pad_t_TOUCH_PAD_NUM8;
+const FILTER_PERIOD_MS: u32 = 10;
+
+/// ESP32 capacitive touch pads, used as buttons.
+///
+/// Touching a pad lowers its reading, so a pad counts as pressed,
+/// when its reading drops below `TOUCH_THRESHOLD_PERCENT` of the value measured at startup.
+pub struct TouchInput {
+    select_threshold: u16,
+    enter_threshold: u16,
+    debounce_select: Debouncer,
+    debounce_enter: Debouncer,
+    pending: VecDeque<ButtonEdge>,
+    start: Instant,
+}
+
+impl TouchInput {
+    /// Pads must not be touched during initialization, as it measures the untouched readings.
+    pub fn new(_select_pin: Gpio32, _enter_pin: Gpio33) -> anyhow::Result<Self> {
+        unsafe {
+            esp!(touch_pad_init())?;
+            esp!(touch_pad_set_fsm_mode(
+                touch_fsm_mode_t_TOUCH_FSM_MODE_TIMER
+            ))?;
+            esp!(touch_pad_config(SELECT_PAD, 0))?;
+            esp!(touch_pad_config(ENTER_PAD, 0))?;
+            esp!(touch_pad_filter_start(FILTER_PERIOD_MS))?;
+        }
+        // Let the filter settle, before taking the untouched readings
+        thread::sleep(Duration::from_millis(FILTER_PERIOD_MS as u64 * 10));
+        let threshold = |pad| -> anyhow::Result<u16> {
+            Ok((read_filtered(pad)? as u32 * TOUCH_THRESHOLD_PERCENT / 100) as u16)
+        };
+
+        Ok(Self {
+            select_threshold: threshold(SELECT_PAD)?,
+            enter_threshold: threshold(ENTER_PAD)?,
+            debounce_select: Debouncer::new(TOUCH_STABLE_TIME),
+            debounce_enter: Debouncer::new(TOUCH_STABLE_TIME),
+            pending: VecDeque::new(),
+            start: Instant::now(),
+        })
+    }
+
+    /// Feeds current pad readings to debouncers and queues debounced edges.
+    fn sample(&mut self) {
+        let now = self.now();
+        let is_touched = |pad, threshold| read_filtered(pad).is_ok_and(|value| value < threshold);
+        let select_touched = is_touched(SELECT_PAD, self.select_threshold);
+        let enter_touched = is_touched(ENTER_PAD, self.enter_threshold);
+        if let Some(edge) = self.debounce_select.update(select_touched, now) {
+            self.pending.push_back(ButtonEdge {
+                button: Button::Select,
+                edge,
+                at: now,
+            });
+        }
+        if let Some(edge) = self.debounce_enter.update(enter_touched, now) {
+            self.pending.push_back(ButtonEdge {
+                button: Button::Enter,
+                edge,
+                at: now,
+            });
+        }
+    }
+}
+
+fn read_filtered(pad: touch_pad_t) -> Result<u16, EspError> {
+    let mut value = 0;
+    esp!(unsafe { touch_pad_read_filtered(pad, &mut value) })?;
+    Ok(value)
+}
+
+impl InputSource for TouchInput {
+    fn next_edge(&mut self, timeout: Option<Duration>) -> Option<ButtonEdge> {
+        // Touch pads have no interrupts wired up, so they are simply polled
+        let deadline = timeout.map(|timeout| self.now() + timeout);
+        loop {
+            if let Some(edge) = self.pending.pop_front() {
+                return Some(edge);
+            }
+            if deadline.is_some_and(|deadline| self.now() >= deadline) {
+                return None;
+            }
+            thread::sleep(TOUCH_POLL_INTERVAL);
+            self.sample();
+        }
+    }
+
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
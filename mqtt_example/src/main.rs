@@ -1,22 +1,50 @@
+mod backlight;
 mod battery;
+mod clock;
 mod config;
 mod controls;
+mod diagnostics;
 mod display;
-mod event;
+mod display_profile;
+mod encoder;
+mod input;
 mod mqtt;
+mod screen;
 mod wifi;
 
-use crate::controls::Controls;
-use crate::display::{DisplayControls, QuizDisplay, QuizRenderer};
-use crate::event::DeviceEvent;
+use crate::backlight::Backlight;
+use crate::config::{
+    BACKLIGHT_DIM_AFTER, CONFETTI_DURATION, DEVICE_NAME, EVENT_RECORDER_CAPACITY, INPUT_SCRIPT,
+    INPUT_SOURCE, PAGE_INTERVAL, ROTARY_ENCODER, THEME, TICK_INTERVAL, TRANSITION_DURATION,
+    WIFI_SSID,
+};
+use crate::diagnostics::{MqttState, NetworkDiagnostics};
+use crate::display::{DisplayControls, QuizDisplay};
+use crate::input::{GpioInput, InputSource, ScriptedInput, TouchInput};
+use crate::screen::Screen;
+use quiz_core::animation::{Animation, Animator};
+use quiz_core::confetti::Confetti;
+use quiz_core::event::DeviceEvent;
+use quiz_core::gesture::Button;
+use quiz_core::markup::Markup;
+use quiz_core::picture::Picture;
+use quiz_core::qr;
+use quiz_core::quiz::{AnswerResult, Question};
+use quiz_core::recorder::{self, EventRecorder};
+use quiz_core::renderer::QuizRenderer;
+use quiz_core::status_bar::StatusBarState;
+use quiz_core::theme::{self, THEMES};
+use quiz_core::wipe::Wipe;
 
 use embedded_svc::mqtt::client::QoS;
 use esp_idf_svc::eventloop::EspSystemEventLoop;
 use esp_idf_svc::hal::peripherals::Peripherals;
 use esp_idf_svc::nvs::EspDefaultNvsPartition;
+use log::{info, warn};
 use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 fn main() -> anyhow::Result<()> {
     esp_idf_svc::sys::link_patches();
@@ -26,14 +54,21 @@ fn main() -> anyhow::Result<()> {
     let nvs = EspDefaultNvsPartition::take()?;
     let peripherals = Peripherals::take()?;
 
-    let mut wifi = wifi::configure(&event_loop, &nvs, peripherals.modem)?;
+    let (sender, receiver) = mpsc::channel();
+    let mut wifi = wifi::configure(&event_loop, &nvs, peripherals.modem, &sender)?;
+    let _wifi_reconnect = wifi::reconnect_on_disconnect(&event_loop, sender.clone())?;
     let device_id = wifi::get_mac(&mut wifi);
-    std::mem::forget(wifi);
-
-    let (mut mqtt_client, mqtt_connection) = mqtt::configure()?;
+    let device_name = DEVICE_NAME.map_or_else(|| device_id.clone(), String::from);
+    let _sntp = clock::start()?;
 
-    let controls = Controls::new(peripherals.pins.gpio0, peripherals.pins.gpio35)?;
+    let broker_url = mqtt::resolve_broker_url();
+    let (mut mqtt_client, mqtt_connection) = mqtt::configure(&broker_url)?;
 
+    let backlight = Backlight::new(
+        peripherals.ledc.channel0,
+        peripherals.ledc.timer0,
+        peripherals.pins.gpio4,
+    )?;
     let mut pixel_buffer = [0_u8; 2048];
     let mut display = QuizDisplay::new(
         peripherals.spi2,
@@ -42,21 +77,61 @@ fn main() -> anyhow::Result<()> {
         peripherals.pins.gpio5,
         peripherals.pins.gpio16,
         peripherals.pins.gpio23,
-        peripherals.pins.gpio4,
+        backlight,
         &mut pixel_buffer,
     );
+    let mut theme = theme::find(THEME);
+    display.set_theme(THEMES[theme]);
     display.clear();
+    display.flush();
     display.off();
 
-    let (sender, receiver) = mpsc::channel();
-    let mut question_id = String::new();
-    let mut question_text = String::new();
-    let mut options: Vec<String> = Default::default();
-    let mut selection: u8 = 0;
-    let mut battery_level: Option<u8> = Some(0);
+    let mut question: Option<Question> = None;
+    // Sent, but not revealed yet
+    let mut last_answer: Option<Question> = None;
+    let mut screen = Screen::Off;
+    let mut status = StatusBarState {
+        battery_level: Some(0),
+        rssi: wifi::get_rssi(),
+        mqtt_state: MqttState::Connecting,
+        time: clock::local_time(),
+    };
+    let mut diagnostics = NetworkDiagnostics::new(WIFI_SSID, broker_url);
+    let mut diagnostics_open = false;
+    let mut recorder = EventRecorder::new(EVENT_RECORDER_CAPACITY);
+    // Long texts are shown page by page, starting over whenever the screen changes
+    let mut page = 0;
+    let mut page_shown = Instant::now();
+    let mut last_tick = Instant::now();
+    let mut last_activity = Instant::now();
+    let mut animator: Option<Animator> = None;
 
     thread::scope(|s| {
-        controls.spawn_thread(s, sender.clone()).unwrap();
+        let make_input = move || -> anyhow::Result<Box<dyn InputSource>> {
+            Ok(match INPUT_SOURCE {
+                Some("touch") => Box::new(TouchInput::new(
+                    peripherals.pins.gpio32,
+                    peripherals.pins.gpio33,
+                )?),
+                Some("script") => Box::new(ScriptedInput::parse(INPUT_SCRIPT)?),
+                _ => Box::new(GpioInput::new(
+                    peripherals.pins.gpio0,
+                    peripherals.pins.gpio35,
+                )?),
+            })
+        };
+        controls::spawn_thread(s, make_input, sender.clone()).unwrap();
+        if ROTARY_ENCODER {
+            encoder::spawn_thread(
+                s,
+                peripherals.pcnt0,
+                peripherals.pins.gpio25,
+                peripherals.pins.gpio26,
+                peripherals.pins.gpio27,
+                sender.clone(),
+            )
+            .unwrap();
+        }
         mqtt::spawn_receiver_thread(s, mqtt_connection, sender.clone()).unwrap();
         battery::spawn_reader_thread(s, peripherals.adc1, peripherals.pins.gpio34, sender.clone())
             .unwrap();
@@ -64,75 +139,311 @@ fn main() -> anyhow::Result<()> {
         mqtt::try_until_subscribed(&mut mqtt_client, "question");
         mqtt::try_until_subscribed(&mut mqtt_client, "sleep");
         mqtt::try_until_subscribed(&mut mqtt_client, "winner");
+        mqtt::try_until_subscribed(&mut mqtt_client, "result");
         mqtt::try_until_subscribed(&mut mqtt_client, "message");
+        mqtt::try_until_subscribed(&mut mqtt_client, "image");
+        mqtt::try_until_subscribed(&mut mqtt_client, "dump");
+        mqtt::try_until_subscribed(&mut mqtt_client, "replay");
 
         loop {
-            let event: DeviceEvent = receiver.recv().unwrap();
+            let mut timeout = TICK_INTERVAL.saturating_sub(last_tick.elapsed());
+            if let Some(animator) = &animator {
+                timeout = timeout.min(animator.until_next_frame(Instant::now()));
+            }
+            let event: DeviceEvent = match receiver.recv_timeout(timeout) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(playing) = &mut animator {
+                        if let Some(area) = playing.next_frame(Instant::now()) {
+                            if !diagnostics_open {
+                                screen.draw_region(
+                                    &mut display,
+                                    area,
+                                    &status,
+                                    question.as_ref(),
+                                    page,
+                                );
+                                if !playing.is_finished() {
+                                    display.draw_animation(playing.animation());
+                                }
+                                display.flush();
+                            }
+                        }
+                        if playing.is_finished() {
+                            animator = None;
+                        }
+                    }
+                    if last_tick.elapsed() < TICK_INTERVAL {
+                        continue;
+                    }
+                    last_tick = Instant::now();
+                    status.rssi = wifi::get_rssi();
+                    status.time = clock::local_time();
+                    if last_activity.elapsed() >= BACKLIGHT_DIM_AFTER {
+                        display.set_dimmed(true);
+                    }
+                    display.draw_status_bar(&status);
+                    display.flush();
+                    if diagnostics_open {
+                        diagnostics::refresh(&mut diagnostics, &wifi);
+                        display.clear();
+                        display.draw_diagnostics(&diagnostics);
+                        display.flush();
+                    } else if animator.is_none()
+                        && display.page_count() > 1
+                        && page_shown.elapsed() >= PAGE_INTERVAL
+                    {
+                        // Turning pages waits for animations, as it redraws the whole screen.
+                        // Screens, that fit on a single page, aren't redrawn, so they don't flicker
+                        page += 1;
+                        page_shown = Instant::now();
+                        screen.draw(&mut display, &status, question.as_ref(), page);
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => panic!("All event senders are gone"),
+            };
+            recorder.record(&event);
+            let won = matches!(event, DeviceEvent::Winner { .. });
+            // Consecutive questions are swapped with a transition, from whatever is shown
+            let next_question = matches!(event, DeviceEvent::Question { .. })
+                && !matches!(screen, Screen::Off)
+                && !diagnostics_open;
+            if event.is_activity() {
+                last_activity = Instant::now();
+                display.set_dimmed(false);
+            }
             match event {
+                DeviceEvent::DumpEvents => {
+                    let dump = recorder.dump();
+                    info!("[Recorder] Last events:\n{dump}");
+                    let topic = format!("events/{}", device_id);
+                    if let Err(e) =
+                        mqtt_client.enqueue(&topic, QoS::AtLeastOnce, false, dump.as_bytes())
+                    {
+                        warn!("[Recorder] Failed to publish events: {e}");
+                    }
+                    continue;
+                }
+                DeviceEvent::Replay { data } => {
+                    match recorder::parse_dump(&data) {
+                        Ok(events) => {
+                            let sender = sender.clone();
+                            thread::Builder::new()
+                                .stack_size(4096)
+                                .spawn_scoped(s, move || recorder::replay(events, &sender))
+                                .unwrap();
+                        }
+                        Err(e) => warn!("[Recorder] Invalid replay: {e}"),
+                    }
+                    continue;
+                }
+                // Pressing both buttons toggles diagnostics
+                DeviceEvent::Chord => {
+                    diagnostics_open = !diagnostics_open;
+                    if diagnostics_open {
+                        diagnostics::refresh(&mut diagnostics, &wifi);
+                        display.clear();
+                        display.draw_diagnostics(&diagnostics);
+                        display.flush();
+                        display.on();
+                        continue;
+                    }
+                }
+                // Any button press closes diagnostics without acting on the previous screen
+                DeviceEvent::Next
+                | DeviceEvent::Previous
+                | DeviceEvent::Confirm
+                | DeviceEvent::LongPress { .. }
+                | DeviceEvent::DoublePress { .. }
+                    if diagnostics_open =>
+                {
+                    diagnostics_open = false;
+                }
+                // Long press of ENTER cancels, dismissing the current message
+                DeviceEvent::LongPress {
+                    data: Button::Enter,
+                } => {
+                    if !matches!(
+                        screen,
+                        Screen::Text { .. }
+                            | Screen::Message { .. }
+                            | Screen::QrCode { .. }
+                            | Screen::Image { .. }
+                            | Screen::Result { .. }
+                    ) {
+                        continue;
+                    }
+                    screen = match question {
+                        Some(_) => Screen::Question,
+                        None => Screen::Idle {
+                            name: device_name.clone(),
+                        },
+                    };
+                }
+                // Double press of ENTER dumps recent events to serial, for bug reports
+                DeviceEvent::DoublePress {
+                    data: Button::Enter,
+                } => {
+                    info!("[Recorder] Last events:\n{}", recorder.dump());
+                    continue;
+                }
+                // Long press of SELECT switches to the next theme
+                DeviceEvent::LongPress {
+                    data: Button::Select,
+                } => {
+                    theme = (theme + 1) % THEMES.len();
+                    info!("[Display] Theme: {}", THEMES[theme].name);
+                    display.set_theme(THEMES[theme]);
+                }
+                DeviceEvent::DoublePress { .. } => continue,
+                DeviceEvent::MqttState { data } => {
+                    diagnostics.mqtt_state = data;
+                    status.mqtt_state = data;
+                    display.draw_status_bar(&status);
+                    display.flush();
+                    continue;
+                }
+                DeviceEvent::NetworkError { data } => {
+                    diagnostics.last_error = Some(data);
+                    continue;
+                }
                 DeviceEvent::Sleep => {
-                    display.clear();
-                    display.draw_battery_level(battery_level);
-                    display.off();
-                    question_id.clear();
+                    screen = Screen::Idle {
+                        name: device_name.clone(),
+                    };
+                    question = None;
                 }
                 DeviceEvent::Question { data } => {
-                    display.clear();
-                    display.draw_battery_level(battery_level);
-                    display.on();
-
-                    let parts: Vec<_> = data.split('|').map(String::from).collect();
-                    question_id = parts[0].clone();
-                    question_text = parts[1].clone();
-                    options = parts[2..6].to_vec();
-
-                    display.draw_question(&question_text);
-                    display.draw_options(&options, selection);
+                    let Some(new_question) = Question::from_payload(&data) else {
+                        warn!("[Quiz] Invalid question `{data}`");
+                        continue;
+                    };
+                    question = Some(new_question);
+                    screen = Screen::Question;
                 }
                 DeviceEvent::Winner { data } => {
-                    if data.into_string() == device_id {
-                        display.clear();
-                        display.draw_battery_level(battery_level);
-                        display.on();
-                        display.draw_text(&format!("You won!\n{}", device_id));
+                    if data.into_string() != device_id {
+                        continue;
                     }
+                    screen = Screen::Text {
+                        text: format!("You won!\n{}", device_id),
+                    };
                 }
-                DeviceEvent::Message { data } => {
-                    display.clear();
-                    display.draw_battery_level(battery_level);
-                    display.draw_text(&String::from(data));
-                    display.on();
+                DeviceEvent::Result { data } => {
+                    let Some(result) = AnswerResult::from_payload(&data) else {
+                        continue;
+                    };
+                    if result.device_id != device_id {
+                        continue;
+                    }
+                    match last_answer.take() {
+                        Some(answer) if answer.id == result.question_id => {
+                            screen = Screen::Result { answer, result };
+                        }
+                        // Result of some other question
+                        other => {
+                            last_answer = other;
+                            continue;
+                        }
+                    }
                 }
-                DeviceEvent::Select { data } => {
-                    selection = data;
-                    if question_id.is_empty() {
-                        display.on();
-                        thread::sleep(Duration::from_millis(1000));
-                        display.off();
+                // Messages starting with `qr:` are shown as a QR code of the rest
+                DeviceEvent::Message { data } => match data.strip_prefix("qr:") {
+                    Some(content) => match qr::encode(content, display.content_area().size) {
+                        Ok(code) => screen = Screen::QrCode { code },
+                        Err(e) => {
+                            warn!("[QR] Failed to encode `{content}`: {e}");
+                            screen = Screen::Text {
+                                text: format!("Can't show QR code: {e}"),
+                            };
+                        }
+                    },
+                    None => {
+                        screen = Screen::Message {
+                            markup: Markup::parse(&data),
+                        };
+                    }
+                },
+                DeviceEvent::Image { data } => {
+                    if let Err(e) = Picture::parse(&data) {
+                        warn!("[Image] Rejected: {e}");
                         continue;
                     }
-                    display.draw_options(&options, selection);
+                    screen = Screen::Image { data };
                 }
-                DeviceEvent::Enter { data } => {
-                    if question_id.is_empty() {
-                        display.on();
-                        thread::sleep(Duration::from_millis(1000));
-                        display.off();
+                DeviceEvent::Next | DeviceEvent::Previous => {
+                    let Some(question) = &mut question else {
+                        if matches!(screen, Screen::Off) {
+                            flash(&mut display);
+                        }
                         continue;
+                    };
+                    match event {
+                        DeviceEvent::Next => question.select_next(),
+                        _ => question.select_previous(),
                     }
-                    let payload = format!("{}|{}|{}", device_id, question_id, data);
+                    if matches!(screen, Screen::Question) {
+                        display.draw_options(&question.options, question.selection(), page);
+                        display.flush();
+                    }
+                    continue;
+                }
+                DeviceEvent::Confirm => {
+                    let Some(answered) = question.take() else {
+                        if matches!(screen, Screen::Off) {
+                            flash(&mut display);
+                        }
+                        continue;
+                    };
+                    let payload = format!("{}|{}|{}", device_id, answered.id, answered.selection());
                     mqtt_client.enqueue("answer", QoS::AtLeastOnce, false, payload.as_bytes())?;
 
-                    display.clear();
-                    display.draw_battery_level(battery_level);
-                    display.draw_text("Answer sent!");
-
-                    question_id.clear();
+                    last_answer = Some(answered);
+                    screen = Screen::Text {
+                        text: String::from("Answer sent!"),
+                    };
                 }
                 DeviceEvent::BatteryLevel { data } => {
-                    battery_level = data;
-                    display.draw_battery_level(battery_level);
+                    status.battery_level = data;
+                    display.draw_status_bar(&status);
+                    display.flush();
+                    continue;
+                }
+            }
+            // Animations stop, whenever the screen changes
+            let animation: Option<Box<dyn Animation>> = if won {
+                Some(Box::new(Confetti::new(
+                    display.content_area(),
+                    CONFETTI_DURATION,
+                )))
+            } else if next_question {
+                Some(Box::new(Wipe::new(
+                    display.content_area(),
+                    TRANSITION_DURATION,
+                )))
+            } else {
+                None
+            };
+            if !diagnostics_open {
+                page = 0;
+                page_shown = Instant::now();
+                // Transitions draw the new screen themselves, over the old one
+                if !animation
+                    .as_ref()
+                    .is_some_and(|animation| animation.is_transition())
+                {
+                    screen.draw(&mut display, &status, question.as_ref(), page);
                 }
             }
+            animator = animation.map(|animation| Animator::new(animation, Instant::now()));
         }
     })
 }
+
+/// Briefly turns the display on, when a button is pressed without a question to answer.
+fn flash<D: DisplayControls>(display: &mut D) {
+    display.on();
+    thread::sleep(Duration::from_millis(1000));
+    display.off();
+}
@@ -1,16 +1,74 @@
-use embedded_svc::mqtt::client::{EventPayload, QoS};
+use embedded_svc::mqtt::client::{Details, EventPayload, QoS};
+use esp_idf_svc::mdns::{EspMdns, Interface, Protocol, QueryResult};
 use esp_idf_svc::mqtt::client::{EspMqttClient, EspMqttConnection, MqttClientConfiguration};
 use esp_idf_svc::sys::esp_crt_bundle_attach;
 use log::*;
+use std::net::IpAddr;
 use std::sync::mpsc;
 use std::thread;
 use std::thread::{sleep, Scope, ScopedJoinHandle};
 use std::time::Duration;
 
-use crate::config::{MQTT_BROKER_URL, MQTT_PASSWORD, MQTT_USER};
-use crate::event::DeviceEvent;
+use crate::config::{
+    MQTT_BROKER_URL, MQTT_MAX_MESSAGE_SIZE, MQTT_MDNS_SERVICE, MQTT_MDNS_TIMEOUT, MQTT_PASSWORD,
+    MQTT_USER,
+};
+use crate::diagnostics::MqttState;
+use quiz_core::event::DeviceEvent;
 
-pub fn configure() -> anyhow::Result<(EspMqttClient<'static>, EspMqttConnection)> {
+/// Returns the broker url announced through mDNS as `MQTT_MDNS_SERVICE`,
+/// falling back to `MQTT_BROKER_URL`.
+pub fn resolve_broker_url() -> String {
+    let broker_url = MQTT_MDNS_SERVICE
+        .and_then(|service| {
+            discover_broker(service)
+                .inspect_err(|e| warn!("[MQTT] mDNS query for {service} failed: {e}"))
+                .ok()
+                .flatten()
+        })
+        .unwrap_or_else(|| String::from(MQTT_BROKER_URL));
+    info!("[MQTT] Using broker {broker_url}");
+    broker_url
+}
+
+/// Queries mDNS for `service` (e.g. `_mqtt._tcp`) and builds url of the first answer.
+///
+/// Plain brokers are connected to by their IPv4 address. Brokers with TLS,
+/// announced as `_secure-mqtt._tcp`, by their `<host>.local` name,
+/// as their certificate is checked against it, it can't match a bare IP.
+fn discover_broker(service: &str) -> anyhow::Result<Option<String>> {
+    let Some((service_type, proto)) = service.rsplit_once('.') else {
+        anyhow::bail!("service should be in `_service._proto` format");
+    };
+    let secure = service_type == "_secure-mqtt";
+
+    let mdns = EspMdns::take()?;
+    let mut results = [QueryResult {
+        instance_name: None,
+        hostname: None,
+        port: 0,
+        txt: Vec::new(),
+        addr: Vec::new(),
+        interface: Interface::STA,
+        ip_protocol: Protocol::V4,
+    }];
+    let found = mdns.query_ptr(service_type, proto, MQTT_MDNS_TIMEOUT, 1, &mut results)?;
+
+    Ok(results[..found].iter().find_map(|result| {
+        debug!("[MQTT] Found {:?} at {:?}", result.hostname, result.addr);
+        if secure {
+            let hostname = result.hostname.as_ref()?;
+            return Some(format!("mqtts://{hostname}.local:{}", result.port));
+        }
+        let addr = result
+            .addr
+            .iter()
+            .find(|addr| matches!(addr, IpAddr::V4(_)))?;
+        Some(format!("mqtt://{addr}:{}", result.port))
+    }))
+}
+
+pub fn configure(broker_url: &str) -> anyhow::Result<(EspMqttClient<'static>, EspMqttConnection)> {
     let mqtt_config = MqttClientConfiguration {
         username: Some(MQTT_USER),
         password: Some(MQTT_PASSWORD),
@@ -22,7 +80,7 @@ pub fn configure() -> anyhow::Result<(EspMqttClient<'static>, EspMqttConnection)
         ..Default::default()
     };
 
-    let (mqtt_client, mqtt_connection) = EspMqttClient::new(MQTT_BROKER_URL, &mqtt_config)?;
+    let (mqtt_client, mqtt_connection) = EspMqttClient::new(broker_url, &mqtt_config)?;
 
     Ok((mqtt_client, mqtt_connection))
 }
@@ -37,26 +95,122 @@ pub fn spawn_receiver_thread<'scope>(
         .stack_size(8192)
         .spawn_scoped(scope, move || loop {
             info!("[MQTT] Listening for messages");
+            let mut partial: Option<PartialMessage> = None;
             while let Ok(event) = mqtt_connection.next() {
                 let payload = event.payload();
                 debug!("[MQTT] {}", payload);
-                if let EventPayload::Received {
-                    id: _,
-                    topic,
-                    data,
-                    details: _,
-                } = payload
-                {
-                    sender
-                        .send(DeviceEvent::from_mqtt_payload(topic.unwrap(), data).unwrap())
-                        .ok();
-                    sleep(Duration::from_millis(500))
+                match payload {
+                    EventPayload::Received {
+                        id: _,
+                        topic,
+                        data,
+                        details,
+                    } => {
+                        let event = match details {
+                            Details::Complete => {
+                                topic.and_then(|topic| DeviceEvent::from_mqtt_payload(topic, data))
+                            }
+                            Details::InitialChunk(chunk) => {
+                                partial = PartialMessage::start(topic, data, chunk.total_data_size);
+                                None
+                            }
+                            Details::SubsequentChunk(chunk) => PartialMessage::append(
+                                &mut partial,
+                                data,
+                                chunk.current_data_offset,
+                                chunk.total_data_size,
+                            ),
+                        };
+                        if let Some(event) = event {
+                            sender.send(event).ok();
+                            sleep(Duration::from_millis(500))
+                        }
+                    }
+                    EventPayload::BeforeConnect => {
+                        sender
+                            .send(DeviceEvent::MqttState {
+                                data: MqttState::Connecting,
+                            })
+                            .ok();
+                    }
+                    EventPayload::Connected(_) => {
+                        sender
+                            .send(DeviceEvent::MqttState {
+                                data: MqttState::Connected,
+                            })
+                            .ok();
+                    }
+                    EventPayload::Disconnected => {
+                        sender
+                            .send(DeviceEvent::MqttState {
+                                data: MqttState::Disconnected,
+                            })
+                            .ok();
+                    }
+                    EventPayload::Error(e) => {
+                        sender
+                            .send(DeviceEvent::NetworkError {
+                                data: format!("MQTT {e}").into_boxed_str(),
+                            })
+                            .ok();
+                    }
+                    _ => {}
                 }
             }
             error!("[MQTT] Connection closed");
         })
 }
 
+/// Message bigger than the MQTT buffer, which arrives in chunks.
+struct PartialMessage {
+    topic: String,
+    data: Vec<u8>,
+}
+
+impl PartialMessage {
+    fn start(topic: Option<&str>, data: &[u8], total_size: usize) -> Option<Self> {
+        if total_size > MQTT_MAX_MESSAGE_SIZE {
+            warn!("[MQTT] Dropping {total_size} B message, the limit is {MQTT_MAX_MESSAGE_SIZE} B");
+            return None;
+        }
+        // The framebuffer competes for the same heap, so running out of it is not fatal
+        let mut buffer = Vec::new();
+        if buffer.try_reserve_exact(total_size).is_err() {
+            warn!("[MQTT] Dropping {total_size} B message, not enough memory");
+            return None;
+        }
+        buffer.extend_from_slice(data);
+        Some(Self {
+            topic: String::from(topic?),
+            data: buffer,
+        })
+    }
+
+    /// Adds a chunk to the message and returns the event, once the whole message arrived.
+    fn append(
+        partial: &mut Option<Self>,
+        data: &[u8],
+        offset: usize,
+        total_size: usize,
+    ) -> Option<DeviceEvent> {
+        let message = partial.as_mut()?;
+        if message.data.len() != offset {
+            warn!(
+                "[MQTT] Chunk of `{}` out of order, dropping message",
+                message.topic
+            );
+            *partial = None;
+            return None;
+        }
+        message.data.extend_from_slice(data);
+        if message.data.len() < total_size {
+            return None;
+        }
+        let message = partial.take()?;
+        DeviceEvent::from_mqtt_payload(&message.topic, &message.data)
+    }
+}
+
 /// Retries subscribing to topic until mqtt connection is established and subscription succeeds.
 pub fn try_until_subscribed(mqtt_client: &mut EspMqttClient, topic: &str) {
     loop {
@@ -0,0 +1,107 @@
+use crate::display::DisplayControls;
+use embedded_graphics::primitives::Rectangle;
+use qrcodegen::QrCode;
+use quiz_core::markup::Markup;
+use quiz_core::quiz::{AnswerResult, Question};
+use quiz_core::renderer::QuizRenderer;
+use quiz_core::status_bar::StatusBarState;
+
+/// Content currently shown on the display.
+/// Kept around, so it can be redrawn after an overlay (e.g. diagnostics) is closed.
+pub enum Screen {
+    Off,
+    /// Logo, device name and clock, shown while waiting for the next question
+    Idle {
+        name: String,
+    },
+    Question,
+    Text {
+        text: String,
+    },
+    /// Message from the organizers, which may contain markup
+    Message {
+        markup: Markup,
+    },
+    QrCode {
+        code: QrCode,
+    },
+    /// Validated BMP or QOI file
+    Image {
+        data: Box<[u8]>,
+    },
+    /// Options of an answered question, with the correct one revealed
+    Result {
+        answer: Question,
+        result: AnswerResult,
+    },
+}
+
+impl Screen {
+    pub fn draw<D>(
+        &self,
+        display: &mut D,
+        status: &StatusBarState,
+        question: Option<&Question>,
+        page: usize,
+    ) where
+        D: DisplayControls + QuizRenderer,
+    {
+        display.clear();
+        display.draw_status_bar(status);
+        self.draw_content(display, status, question, page);
+        display.flush();
+        match self {
+            Screen::Off => display.off(),
+            _ => display.on(),
+        }
+    }
+
+    /// Redraws only `area` of the content, e.g. where an animation was, without flushing.
+    pub fn draw_region<D>(
+        &self,
+        display: &mut D,
+        area: Rectangle,
+        status: &StatusBarState,
+        question: Option<&Question>,
+        page: usize,
+    ) where
+        D: DisplayControls + QuizRenderer,
+    {
+        display.set_clip(Some(area));
+        display.clear();
+        self.draw_content(display, status, question, page);
+        display.set_clip(None);
+    }
+
+    fn draw_content<D>(
+        &self,
+        display: &mut D,
+        status: &StatusBarState,
+        question: Option<&Question>,
+        page: usize,
+    ) where
+        D: QuizRenderer,
+    {
+        match self {
+            Screen::Off => {}
+            Screen::Idle { name } => display.draw_idle(name, status.time),
+            Screen::Question => {
+                if let Some(question) = question {
+                    display.draw_question(&question.text, question.progress.as_ref(), page);
+                    display.draw_options(&question.options, question.selection(), page);
+                }
+            }
+            Screen::Text { text } => display.draw_text(text, page),
+            Screen::Message { markup } => display.draw_markup(markup, page),
+            Screen::QrCode { code } => display.draw_qr_code(code),
+            Screen::Image { data } => display.draw_image(data),
+            Screen::Result { answer, result } => display.draw_result(
+                &answer.options,
+                answer.selection(),
+                result.correct,
+                result.points,
+                page,
+            ),
+        }
+    }
+}
@@ -1,16 +1,23 @@
-use crate::config::{WIFI_PASSWORD, WIFI_SSID};
+use crate::config::{WIFI_PASSWORD, WIFI_RETRY_INTERVAL, WIFI_SSID};
+use quiz_core::event::DeviceEvent;
 
 use embedded_svc::wifi::{ClientConfiguration, Configuration};
-use esp_idf_svc::eventloop::EspSystemEventLoop;
+use esp_idf_svc::eventloop::{EspSubscription, EspSystemEventLoop, System};
 use esp_idf_svc::hal::modem::Modem;
 use esp_idf_svc::nvs::EspDefaultNvsPartition;
-use esp_idf_svc::wifi::{BlockingWifi, EspWifi, WifiDeviceId};
-use log::info;
+use esp_idf_svc::sys::{esp, esp_wifi_connect, esp_wifi_sta_get_ap_info, wifi_ap_record_t};
+use esp_idf_svc::wifi::{BlockingWifi, EspWifi, WifiDeviceId, WifiEvent};
+use log::{info, warn};
+use std::sync::mpsc;
+use std::thread;
 
+/// Connects to the access point, retrying until it succeeds.
+/// Failures are sent to mpsc channel as `NetworkError`, so they show up in diagnostics.
 pub fn configure(
     event_loop: &EspSystemEventLoop,
     nvs: &EspDefaultNvsPartition,
     modem: Modem,
+    sender: &mpsc::Sender<DeviceEvent>,
 ) -> anyhow::Result<BlockingWifi<EspWifi<'static>>> {
     let mut wifi = BlockingWifi::wrap(
         EspWifi::new(modem, event_loop.clone(), Some(nvs.clone()))?,
@@ -23,13 +30,50 @@ pub fn configure(
         ..Default::default()
     }))?;
     wifi.start()?;
-    wifi.connect()?;
-    wifi.wait_netif_up()?;
+    while let Err(e) = wifi.connect().and_then(|_| wifi.wait_netif_up()) {
+        warn!("[WIFI] Failed to connect: {e}");
+        sender
+            .send(DeviceEvent::NetworkError {
+                data: format!("Wi-Fi {e}").into_boxed_str(),
+            })
+            .ok();
+        thread::sleep(WIFI_RETRY_INTERVAL);
+    }
     info!("[WIFI] Connected");
     Ok(wifi)
 }
 
+/// Reconnects, whenever the connection to the access point is lost,
+/// sending the reason as `NetworkError`. Works as long as the subscription is kept.
+pub fn reconnect_on_disconnect(
+    event_loop: &EspSystemEventLoop,
+    sender: mpsc::Sender<DeviceEvent>,
+) -> anyhow::Result<EspSubscription<'static, System>> {
+    Ok(event_loop.subscribe::<WifiEvent, _>(move |event| {
+        if let WifiEvent::StaDisconnected(disconnected) = event {
+            warn!("[WIFI] Disconnected, reason {}", disconnected.reason());
+            sender
+                .send(DeviceEvent::NetworkError {
+                    data: format!("Wi-Fi disconnected, reason {}", disconnected.reason())
+                        .into_boxed_str(),
+                })
+                .ok();
+            // Failed attempts end with another disconnection, which retries again
+            if let Err(e) = esp!(unsafe { esp_wifi_connect() }) {
+                warn!("[WIFI] Failed to reconnect: {e}");
+            }
+        }
+    })?)
+}
+
 pub fn get_mac(wifi: &mut BlockingWifi<EspWifi>) -> String {
     let mac_bytes = wifi.wifi_mut().get_mac(WifiDeviceId::Sta).unwrap();
     mac_bytes.map(|b| format!("{:X}", b)).join(":")
 }
+
+/// Returns signal strength (in dBm) of the access point, the station is connected to.
+pub fn get_rssi() -> Option<i8> {
+    let mut ap_info = wifi_ap_record_t::default();
+    esp!(unsafe { esp_wifi_sta_get_ap_info(&mut ap_info) }).ok()?;
+    Some(ap_info.rssi)
+}
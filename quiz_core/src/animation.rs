@@ -0,0 +1,240 @@
+use crate::config::ANIMATION_FRAME_INTERVAL;
+use crate::theme::Theme;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+
+/// Object safe part of `DrawTarget<Color = Rgb565>`, so animations can be boxed.
+/// Implemented for every such target, and `dyn Canvas` is a `DrawTarget` again.
+pub trait Canvas {
+    fn canvas_bounds(&self) -> Rectangle;
+    fn draw_pixels(&mut self, pixels: &mut dyn Iterator<Item = Pixel<Rgb565>>);
+    fn fill_area(&mut self, area: &Rectangle, color: Rgb565);
+}
+
+impl<D> Canvas for D
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    fn canvas_bounds(&self) -> Rectangle {
+        self.bounding_box()
+    }
+
+    fn draw_pixels(&mut self, pixels: &mut dyn Iterator<Item = Pixel<Rgb565>>) {
+        self.draw_iter(pixels).ok();
+    }
+
+    fn fill_area(&mut self, area: &Rectangle, color: Rgb565) {
+        self.fill_solid(area, color).ok();
+    }
+}
+
+impl Dimensions for dyn Canvas + '_ {
+    fn bounding_box(&self) -> Rectangle {
+        self.canvas_bounds()
+    }
+}
+
+impl DrawTarget for dyn Canvas + '_ {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.draw_pixels(&mut pixels.into_iter());
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill_area(area, color);
+        Ok(())
+    }
+}
+
+/// Something drawn over the current screen frame by frame, e.g. confetti or a transition.
+pub trait Animation {
+    /// Moves the animation `elapsed` forward. Returns `false` once it's over.
+    fn advance(&mut self, elapsed: Duration) -> bool;
+    /// Area covered by the current frame
+    fn bounds(&self) -> Rectangle;
+    fn draw(&self, canvas: &mut dyn Canvas, theme: &Theme);
+    /// Transitions start over the outgoing screen, which is left on the display,
+    /// and reveal the incoming one, as it's redrawn in their bounds.
+    /// Other animations are drawn over a complete screen.
+    fn is_transition(&self) -> bool {
+        false
+    }
+}
+
+/// Plays an `Animation` at `ANIMATION_FRAME_INTERVAL`.
+///
+/// Each frame invalidates the area covered by the previous one and the new one,
+/// only that has to be redrawn (without the animation), before drawing the new frame.
+pub struct Animator {
+    animation: Box<dyn Animation>,
+    last_frame: Instant,
+    /// Covered by the last drawn frame
+    previous_bounds: Rectangle,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new(animation: Box<dyn Animation>, now: Instant) -> Self {
+        Self {
+            previous_bounds: animation.bounds(),
+            animation,
+            last_frame: now,
+            finished: false,
+        }
+    }
+
+    pub fn animation(&self) -> &dyn Animation {
+        self.animation.as_ref()
+    }
+
+    /// Once finished, the last invalidated area should be redrawn without the animation.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Time until the next frame is due, to be used as a timeout while waiting for events.
+    pub fn until_next_frame(&self, now: Instant) -> Duration {
+        ANIMATION_FRAME_INTERVAL.saturating_sub(now.saturating_duration_since(self.last_frame))
+    }
+
+    /// Advances the animation, if the next frame is due at `now`,
+    /// returning the area invalidated by it.
+    pub fn next_frame(&mut self, now: Instant) -> Option<Rectangle> {
+        if self.finished || !self.until_next_frame(now).is_zero() {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.last_frame);
+        self.last_frame = now;
+        self.finished = !self.animation.advance(elapsed);
+        let bounds = self.animation.bounds();
+        let invalidated = union(self.previous_bounds, bounds);
+        self.previous_bounds = bounds;
+        Some(invalidated)
+    }
+}
+
+/// Smallest rectangle containing both `a` and `b`, empty ones are ignored.
+pub fn union(a: Rectangle, b: Rectangle) -> Rectangle {
+    match (a.bottom_right(), b.bottom_right()) {
+        (Some(a_bottom_right), Some(b_bottom_right)) => Rectangle::with_corners(
+            a.top_left.component_min(b.top_left),
+            a_bottom_right.component_max(b_bottom_right),
+        ),
+        (Some(_), None) => a,
+        (None, _) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Square moving one pixel right per frame interval, for `frames` frames
+    struct Slide {
+        x: i32,
+        frames: u32,
+    }
+
+    impl Animation for Slide {
+        fn advance(&mut self, elapsed: Duration) -> bool {
+            self.x += (elapsed.as_millis() / ANIMATION_FRAME_INTERVAL.as_millis()) as i32;
+            self.frames -= 1;
+            self.frames > 0
+        }
+
+        fn bounds(&self) -> Rectangle {
+            Rectangle::new(Point::new(self.x, 0), Size::new(2, 2))
+        }
+
+        fn draw(&self, _canvas: &mut dyn Canvas, _theme: &Theme) {}
+    }
+
+    fn slide(frames: u32) -> Box<Slide> {
+        Box::new(Slide { x: 0, frames })
+    }
+
+    #[test]
+    fn union_covers_both() {
+        let a = Rectangle::new(Point::new(0, 0), Size::new(2, 2));
+        let b = Rectangle::new(Point::new(5, 3), Size::new(2, 2));
+        let expected = Rectangle::new(Point::new(0, 0), Size::new(7, 5));
+        assert_eq!(union(a, b), expected);
+        assert_eq!(union(b, a), expected);
+    }
+
+    #[test]
+    fn union_ignores_empty() {
+        let a = Rectangle::new(Point::new(3, 4), Size::new(2, 2));
+        let empty = Rectangle::new(Point::new(100, 100), Size::zero());
+        assert_eq!(union(a, empty), a);
+        assert_eq!(union(empty, a), a);
+        assert_eq!(union(empty, empty), empty);
+    }
+
+    #[test]
+    fn frames_wait_for_interval() {
+        let start = Instant::now();
+        let mut animator = Animator::new(slide(10), start);
+        assert_eq!(animator.until_next_frame(start), ANIMATION_FRAME_INTERVAL);
+        let early = start + ANIMATION_FRAME_INTERVAL / 2;
+        assert_eq!(
+            animator.until_next_frame(early),
+            ANIMATION_FRAME_INTERVAL / 2
+        );
+        assert_eq!(animator.next_frame(early), None);
+        let due = start + ANIMATION_FRAME_INTERVAL;
+        assert!(animator.until_next_frame(due).is_zero());
+        assert!(animator.next_frame(due).is_some());
+        assert_eq!(animator.next_frame(due), None);
+    }
+
+    #[test]
+    fn frame_invalidates_previous_and_current_bounds() {
+        let start = Instant::now();
+        let mut animator = Animator::new(slide(10), start);
+        assert_eq!(
+            animator.next_frame(start + ANIMATION_FRAME_INTERVAL),
+            Some(Rectangle::new(Point::zero(), Size::new(3, 2)))
+        );
+        assert_eq!(
+            animator.next_frame(start + ANIMATION_FRAME_INTERVAL * 2),
+            Some(Rectangle::new(Point::new(1, 0), Size::new(3, 2)))
+        );
+    }
+
+    #[test]
+    fn late_frames_advance_by_actual_time() {
+        let start = Instant::now();
+        let mut animator = Animator::new(slide(10), start);
+        let late = start + ANIMATION_FRAME_INTERVAL * 3;
+        animator.next_frame(late);
+        assert_eq!(animator.animation().bounds().top_left.x, 3);
+        animator.next_frame(late + ANIMATION_FRAME_INTERVAL);
+        assert_eq!(animator.animation().bounds().top_left.x, 4);
+    }
+
+    #[test]
+    fn finishes_after_last_frame() {
+        let start = Instant::now();
+        let mut animator = Animator::new(slide(2), start);
+        animator.next_frame(start + ANIMATION_FRAME_INTERVAL);
+        assert!(!animator.is_finished());
+        assert!(animator
+            .next_frame(start + ANIMATION_FRAME_INTERVAL * 2)
+            .is_some());
+        assert!(animator.is_finished());
+        assert_eq!(
+            animator.next_frame(start + ANIMATION_FRAME_INTERVAL * 3),
+            None
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::animation::{self, Animation, Canvas};
+use crate::theme::Theme;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PIECES: usize = 32;
+const PIECE_SIZE: Size = Size::new(4, 3);
+const COLORS: [Rgb565; 5] = [
+    Rgb565::RED,
+    Rgb565::GREEN,
+    Rgb565::BLUE,
+    Rgb565::YELLOW,
+    Rgb565::MAGENTA,
+];
+/// In pixels per second squared, low enough for the pieces to float
+const GRAVITY: f32 = 40.0;
+
+struct Piece {
+    x: f32,
+    y: f32,
+    /// Velocity in pixels per second
+    dx: f32,
+    dy: f32,
+    color: Rgb565,
+}
+
+impl Piece {
+    fn rectangle(&self) -> Rectangle {
+        Rectangle::new(Point::new(self.x as i32, self.y as i32), PIECE_SIZE)
+    }
+}
+
+/// Colorful pieces falling from above the top of `area`, for winners.
+pub struct Confetti {
+    area: Rectangle,
+    pieces: Vec<Piece>,
+    remaining: Duration,
+}
+
+impl Confetti {
+    pub fn new(area: Rectangle, duration: Duration) -> Self {
+        // Doesn't have to be good randomness, just different every time
+        let mut random = Xorshift(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(1, |time| time.subsec_nanos() | 1),
+        );
+        let width = area.size.width as f32;
+        let height = area.size.height as f32;
+        let pieces = (0..PIECES)
+            .map(|idx| Piece {
+                x: area.top_left.x as f32 + random.next_f32() * width,
+                // Spread out above the area, so they don't all arrive at once
+                y: area.top_left.y as f32 - random.next_f32() * height,
+                dx: (random.next_f32() - 0.5) * 30.0,
+                dy: 20.0 + random.next_f32() * 40.0,
+                color: COLORS[idx % COLORS.len()],
+            })
+            .collect();
+        Self {
+            area,
+            pieces,
+            remaining: duration,
+        }
+    }
+}
+
+impl Animation for Confetti {
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        self.remaining = self.remaining.saturating_sub(elapsed);
+        let seconds = elapsed.as_secs_f32();
+        for piece in &mut self.pieces {
+            piece.dy += GRAVITY * seconds;
+            piece.x += piece.dx * seconds;
+            piece.y += piece.dy * seconds;
+        }
+        let bottom = (self.area.top_left.y + self.area.size.height as i32) as f32;
+        self.pieces.retain(|piece| piece.y < bottom);
+        !self.remaining.is_zero() && !self.pieces.is_empty()
+    }
+
+    fn bounds(&self) -> Rectangle {
+        self.pieces
+            .iter()
+            .map(|piece| piece.rectangle().intersection(&self.area))
+            .fold(Rectangle::zero(), animation::union)
+    }
+
+    fn draw(&self, canvas: &mut dyn Canvas, _theme: &Theme) {
+        for piece in &self.pieces {
+            canvas
+                .fill_solid(&piece.rectangle().intersection(&self.area), piece.color)
+                .ok();
+        }
+    }
+}
+
+/// Xorshift pseudo-random number generator, seed must not be 0.
+struct Xorshift(u32);
+
+impl Xorshift {
+    /// Returns a number in `0.0..1.0`.
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+}
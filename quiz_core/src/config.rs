@@ -0,0 +1,10 @@
+//! Settings of the UI. Device specific ones (Wi-Fi, MQTT, pins) live in `mqtt_example`.
+
+use std::time::Duration;
+
+/// Images bigger than the biggest supported panel are rejected
+pub const MAX_IMAGE_SIZE: (u32, u32) = (320, 320);
+/// Time between frames of animations, 25 per second
+pub const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(40);
+/// Battery level (in %) shown with the theme's warning color
+pub const LOW_BATTERY_LEVEL: u8 = 15;
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    Pressed,
+    Released,
+}
+
+/// Debounces a single button by requiring its level to stay the same for `stable_time`,
+/// before reporting a press or a release.
+///
+/// Timestamps are plain `Duration`s since an arbitrary point (e.g. boot),
+/// so the state machine doesn't depend on any clock source.
+pub struct Debouncer {
+    stable_time: Duration,
+    /// Debounced state, as reported to the caller
+    pressed: bool,
+    /// Last sampled state and when it was sampled for the first time
+    raw_pressed: bool,
+    raw_since: Duration,
+}
+
+impl Debouncer {
+    pub const fn new(stable_time: Duration) -> Self {
+        Self {
+            stable_time,
+            pressed: false,
+            raw_pressed: false,
+            raw_since: Duration::ZERO,
+        }
+    }
+
+    /// Feeds a sample of the button state taken at `now`.
+    /// Returns an edge, once the new state has been stable long enough.
+    pub fn update(&mut self, pressed: bool, now: Duration) -> Option<Edge> {
+        if pressed != self.raw_pressed {
+            self.raw_pressed = pressed;
+            self.raw_since = now;
+        }
+        if self.raw_pressed == self.pressed || now.saturating_sub(self.raw_since) < self.stable_time
+        {
+            return None;
+        }
+        self.pressed = self.raw_pressed;
+        Some(if self.pressed {
+            Edge::Pressed
+        } else {
+            Edge::Released
+        })
+    }
+
+    /// Returns `true`, if the last sample differs from the debounced state
+    /// and more samples are needed to settle.
+    pub fn is_settling(&self) -> bool {
+        self.raw_pressed != self.pressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STABLE_TIME: Duration = Duration::from_millis(20);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn reports_press_once_stable() {
+        let mut debouncer = Debouncer::new(STABLE_TIME);
+        assert_eq!(debouncer.update(true, ms(0)), None);
+        assert!(debouncer.is_settling());
+        assert_eq!(debouncer.update(true, ms(10)), None);
+        assert_eq!(debouncer.update(true, ms(20)), Some(Edge::Pressed));
+        assert!(!debouncer.is_settling());
+        assert_eq!(debouncer.update(true, ms(30)), None);
+    }
+
+    #[test]
+    fn suppresses_bounces() {
+        let mut debouncer = Debouncer::new(STABLE_TIME);
+        for (pressed, at) in [(true, 0), (false, 5), (true, 10), (false, 15), (true, 18)] {
+            assert_eq!(debouncer.update(pressed, ms(at)), None);
+        }
+        assert_eq!(debouncer.update(true, ms(30)), None);
+        assert_eq!(debouncer.update(true, ms(38)), Some(Edge::Pressed));
+    }
+
+    #[test]
+    fn ignores_glitches_shorter_than_stable_time() {
+        let mut debouncer = Debouncer::new(STABLE_TIME);
+        assert_eq!(debouncer.update(true, ms(0)), None);
+        assert_eq!(debouncer.update(false, ms(15)), None);
+        assert_eq!(debouncer.update(false, ms(50)), None);
+        assert!(!debouncer.is_settling());
+    }
+
+    #[test]
+    fn reports_release() {
+        let mut debouncer = Debouncer::new(STABLE_TIME);
+        debouncer.update(true, ms(0));
+        debouncer.update(true, ms(20));
+        assert_eq!(debouncer.update(false, ms(100)), None);
+        assert_eq!(debouncer.update(true, ms(105)), None);
+        assert_eq!(debouncer.update(false, ms(110)), None);
+        assert_eq!(debouncer.update(false, ms(130)), Some(Edge::Released));
+    }
+}
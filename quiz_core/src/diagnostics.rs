@@ -0,0 +1,80 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MqttState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl fmt::Display for MqttState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MqttState::Connecting => "connecting",
+            MqttState::Connected => "connected",
+            MqttState::Disconnected => "disconnected",
+        })
+    }
+}
+
+/// Snapshot of everything, that can go wrong between the device and the broker.
+/// Filled in by the device, this only knows how to present it.
+pub struct NetworkDiagnostics {
+    pub ssid: String,
+    pub broker_url: String,
+    pub mqtt_state: MqttState,
+    pub last_error: Option<Box<str>>,
+    pub rssi: Option<i8>,
+    pub ip: Option<Ipv4Addr>,
+    pub gateway: Option<Ipv4Addr>,
+    pub uptime: Duration,
+    pub free_heap: u32,
+}
+
+impl NetworkDiagnostics {
+    pub fn new(ssid: &str, broker_url: String) -> Self {
+        Self {
+            ssid: String::from(ssid),
+            broker_url,
+            mqtt_state: MqttState::Connecting,
+            last_error: None,
+            rssi: None,
+            ip: None,
+            gateway: None,
+            uptime: Duration::ZERO,
+            free_heap: 0,
+        }
+    }
+}
+
+impl fmt::Display for NetworkDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SSID: {}", self.ssid)?;
+        match self.rssi {
+            Some(rssi) => writeln!(f, "RSSI: {} dBm", rssi)?,
+            None => writeln!(f, "RSSI: -")?,
+        }
+        match self.ip {
+            Some(ip) => writeln!(f, "IP: {}", ip)?,
+            None => writeln!(f, "IP: -")?,
+        }
+        match self.gateway {
+            Some(gateway) => writeln!(f, "GW: {}", gateway)?,
+            None => writeln!(f, "GW: -")?,
+        }
+        writeln!(f, "MQTT: {}", self.mqtt_state)?;
+        writeln!(f, "{}", self.broker_url)?;
+        writeln!(f, "Err: {}", self.last_error.as_deref().unwrap_or("-"))?;
+        let uptime = self.uptime.as_secs();
+        writeln!(
+            f,
+            "Up: {}h {:02}m {:02}s",
+            uptime / 3600,
+            uptime / 60 % 60,
+            uptime % 60
+        )?;
+        write!(f, "Heap: {} B", self.free_heap)
+    }
+}
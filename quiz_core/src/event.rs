@@ -0,0 +1,73 @@
+use crate::diagnostics::MqttState;
+use crate::gesture::Button;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceEvent {
+    // MQTT events
+    Sleep,
+    Question { data: Box<str> },
+    Winner { data: Box<str> },
+    Message { data: Box<str> },
+    Image { data: Box<[u8]> },
+    Result { data: Box<str> },
+    DumpEvents,
+    Replay { data: Box<str> },
+    // MQTT connection events
+    MqttState { data: MqttState },
+    NetworkError { data: Box<str> },
+    // Button events
+    Next,
+    Previous,
+    Confirm,
+    LongPress { data: Button },
+    DoublePress { data: Button },
+    Chord,
+    // Battery reader events
+    BatteryLevel { data: Option<u8> },
+}
+
+impl DeviceEvent {
+    /// Whether the event comes from the player or brings new content,
+    /// as opposed to background updates (battery, connection state, ...).
+    pub fn is_activity(&self) -> bool {
+        matches!(
+            self,
+            DeviceEvent::Sleep
+                | DeviceEvent::Question { .. }
+                | DeviceEvent::Winner { .. }
+                | DeviceEvent::Message { .. }
+                | DeviceEvent::Image { .. }
+                | DeviceEvent::Result { .. }
+                | DeviceEvent::Next
+                | DeviceEvent::Previous
+                | DeviceEvent::Confirm
+                | DeviceEvent::LongPress { .. }
+                | DeviceEvent::DoublePress { .. }
+                | DeviceEvent::Chord
+        )
+    }
+
+    pub fn from_mqtt_payload(topic: &str, data: &[u8]) -> Option<Self> {
+        match topic {
+            "sleep" => Some(DeviceEvent::Sleep),
+            "question" => Some(DeviceEvent::Question {
+                data: String::from_utf8_lossy(data).into_owned().into_boxed_str(),
+            }),
+            "winner" => Some(DeviceEvent::Winner {
+                data: String::from_utf8_lossy(data).into_owned().into_boxed_str(),
+            }),
+            "message" => Some(DeviceEvent::Message {
+                data: String::from_utf8_lossy(data).into_owned().into_boxed_str(),
+            }),
+            "image" => Some(DeviceEvent::Image { data: data.into() }),
+            "result" => Some(DeviceEvent::Result {
+                data: String::from_utf8_lossy(data).into_owned().into_boxed_str(),
+            }),
+            "dump" => Some(DeviceEvent::DumpEvents),
+            "replay" => Some(DeviceEvent::Replay {
+                data: String::from_utf8_lossy(data).into_owned().into_boxed_str(),
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Pure filtering steps applied to battery ADC readings, independent of the hardware.
+
+/// Median of `samples`, which get sorted in the process.
+/// Single spikes, e.g. from Wi-Fi transmissions, don't affect it at all.
+pub fn median(samples: &mut [u16]) -> u16 {
+    samples.sort_unstable();
+    samples[samples.len() / 2]
+}
+
+/// Exponential moving average, smoothing out slower changes of the load.
+pub struct MovingAverage {
+    /// Weight of a new value
+    alpha: f32,
+    average: Option<f32>,
+}
+
+impl MovingAverage {
+    pub fn new(alpha: f32) -> Self {
+        Self {
+            alpha,
+            average: None,
+        }
+    }
+
+    pub fn update(&mut self, value: u16) -> u16 {
+        let average = match self.average {
+            Some(average) => average + self.alpha * (value as f32 - average),
+            None => value as f32,
+        };
+        self.average = Some(average);
+        average.round() as u16
+    }
+
+    /// Starts over from the next value.
+    pub fn reset(&mut self) {
+        self.average = None;
+    }
+}
+
+/// Keeps the reported level, until the new one differs by at least `threshold`,
+/// so it doesn't flicker between two neighbouring values.
+pub struct Hysteresis {
+    threshold: u8,
+    reported: Option<u8>,
+}
+
+impl Hysteresis {
+    pub fn new(threshold: u8) -> Self {
+        Self {
+            threshold,
+            reported: None,
+        }
+    }
+
+    pub fn update(&mut self, level: u8) -> u8 {
+        match self.reported {
+            // Full and empty battery are always reported, otherwise they might never be
+            Some(reported)
+                if reported.abs_diff(level) < self.threshold && level != 0 && level != 100 =>
+            {
+                reported
+            }
+            _ => {
+                self.reported = Some(level);
+                level
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.reported = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_rejects_spikes() {
+        let mut samples = [1850, 1852, 3100, 1849, 1851, 20, 1850];
+        assert_eq!(median(&mut samples), 1850);
+    }
+
+    #[test]
+    fn median_of_single_sample() {
+        assert_eq!(median(&mut [1234]), 1234);
+    }
+
+    #[test]
+    fn moving_average_starts_at_first_value() {
+        let mut average = MovingAverage::new(0.2);
+        assert_eq!(average.update(1800), 1800);
+    }
+
+    #[test]
+    fn moving_average_converges() {
+        let mut average = MovingAverage::new(0.2);
+        average.update(1800);
+        let first_step = average.update(2000);
+        assert!(first_step > 1800 && first_step < 1900, "{first_step}");
+        let settled = (0..50).map(|_| average.update(2000)).last().unwrap();
+        assert_eq!(settled, 2000);
+    }
+
+    #[test]
+    fn moving_average_reset_starts_over() {
+        let mut average = MovingAverage::new(0.2);
+        average.update(1800);
+        average.reset();
+        assert_eq!(average.update(2100), 2100);
+    }
+
+    #[test]
+    fn hysteresis_holds_small_changes() {
+        let mut hysteresis = Hysteresis::new(3);
+        assert_eq!(hysteresis.update(50), 50);
+        assert_eq!(hysteresis.update(52), 50);
+        assert_eq!(hysteresis.update(48), 50);
+    }
+
+    #[test]
+    fn hysteresis_releases_bigger_changes() {
+        let mut hysteresis = Hysteresis::new(3);
+        hysteresis.update(50);
+        assert_eq!(hysteresis.update(47), 47);
+        assert_eq!(hysteresis.update(49), 47);
+        assert_eq!(hysteresis.update(51), 51);
+    }
+
+    #[test]
+    fn hysteresis_always_reports_full_and_empty() {
+        let mut hysteresis = Hysteresis::new(3);
+        hysteresis.update(99);
+        assert_eq!(hysteresis.update(100), 100);
+        hysteresis.update(2);
+        assert_eq!(hysteresis.update(1), 2);
+        assert_eq!(hysteresis.update(0), 0);
+    }
+
+    #[test]
+    fn hysteresis_reset_reports_next_level() {
+        let mut hysteresis = Hysteresis::new(3);
+        hysteresis.update(50);
+        hysteresis.reset();
+        assert_eq!(hysteresis.update(51), 51);
+    }
+}
@@ -0,0 +1,417 @@
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use std::convert::Infallible;
+
+/// Dirty regions are tracked in tiles of `TILE_SIZE`x`TILE_SIZE` pixels.
+/// Smaller tiles send less unchanged pixels, but produce more (slower to set up) SPI transfers.
+const TILE_SIZE: u32 = 16;
+
+/// Off-screen copy of the panel contents.
+///
+/// Everything is drawn here first, and only tiles, that actually changed color,
+/// are sent to the panel on `flush`. Clearing and redrawing a whole screen
+/// with mostly the same contents is therefore cheap and doesn't flicker.
+///
+/// Pixels live on the heap, so with PSRAM enabled (`CONFIG_SPIRAM_USE_MALLOC`)
+/// the buffer is placed there automatically.
+/// Without PSRAM, big panels (e.g. 240x320, ~150 KB) may not fit, see `BufferedPanel`.
+pub struct Framebuffer {
+    size: Size,
+    pixels: Vec<Rgb565>,
+    tiles: Size,
+    dirty: Vec<bool>,
+    /// Drawing outside of it is ignored
+    clip: Rectangle,
+}
+
+impl Framebuffer {
+    /// Returns `None`, if there isn't enough memory for the pixels.
+    pub fn try_new(size: Size) -> Option<Self> {
+        let tiles = Size::new(
+            size.width.div_ceil(TILE_SIZE),
+            size.height.div_ceil(TILE_SIZE),
+        );
+        let len = (size.width * size.height) as usize;
+        let mut pixels = Vec::new();
+        pixels.try_reserve_exact(len).ok()?;
+        pixels.resize(len, Rgb565::BLACK);
+        Some(Self {
+            size,
+            pixels,
+            tiles,
+            // Contents of the panel are unknown, so everything has to be sent at first
+            dirty: vec![true; (tiles.width * tiles.height) as usize],
+            clip: Rectangle::new(Point::zero(), size),
+        })
+    }
+
+    /// Limits drawing to `area`, e.g. to redraw only part of a screen. `None` resets it.
+    pub fn set_clip(&mut self, area: Option<Rectangle>) {
+        let bounds = self.bounding_box();
+        self.clip = area.map_or(bounds, |area| area.intersection(&bounds));
+    }
+
+    fn set_pixel(&mut self, point: Point, color: Rgb565) {
+        let (x, y) = (point.x as u32, point.y as u32);
+        let pixel = &mut self.pixels[(y * self.size.width + x) as usize];
+        if *pixel != color {
+            *pixel = color;
+            self.dirty[((y / TILE_SIZE) * self.tiles.width + x / TILE_SIZE) as usize] = true;
+        }
+    }
+
+    /// Joins dirty tiles into rectangles: horizontal runs of tiles first,
+    /// then runs of the same width stacked on top of each other.
+    fn dirty_rectangles(&self) -> Vec<Rectangle> {
+        let mut rectangles: Vec<Rectangle> = Vec::new();
+        for tile_y in 0..self.tiles.height {
+            let row =
+                &self.dirty[(tile_y * self.tiles.width) as usize..][..self.tiles.width as usize];
+            let mut tile_x = 0;
+            while tile_x < row.len() {
+                if !row[tile_x] {
+                    tile_x += 1;
+                    continue;
+                }
+                let start = tile_x;
+                while tile_x < row.len() && row[tile_x] {
+                    tile_x += 1;
+                }
+                let run = Rectangle::new(
+                    Point::new(
+                        (start as u32 * TILE_SIZE) as i32,
+                        (tile_y * TILE_SIZE) as i32,
+                    ),
+                    Size::new((tile_x - start) as u32 * TILE_SIZE, TILE_SIZE),
+                );
+                let above = rectangles.iter_mut().find(|rectangle| {
+                    rectangle.top_left.x == run.top_left.x
+                        && rectangle.size.width == run.size.width
+                        && rectangle.top_left.y + rectangle.size.height as i32 == run.top_left.y
+                });
+                match above {
+                    Some(rectangle) => rectangle.size.height += TILE_SIZE,
+                    None => rectangles.push(run),
+                }
+            }
+        }
+        // Tiles on the right and bottom edge may stick out of the buffer
+        let bounds = self.bounding_box();
+        rectangles
+            .iter()
+            .map(|rectangle| rectangle.intersection(&bounds))
+            .collect()
+    }
+
+    /// Sends changed regions to `target`, which should be the same size as the buffer.
+    pub fn flush<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        for rectangle in self.dirty_rectangles() {
+            let width = self.size.width;
+            let colors = rectangle
+                .points()
+                .map(|point| self.pixels[(point.y as u32 * width + point.x as u32) as usize]);
+            target.fill_contiguous(&rectangle, colors)?;
+        }
+        self.dirty.fill(false);
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if self.clip.contains(point) {
+                self.set_pixel(point, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        for point in area.intersection(&self.clip).points() {
+            self.set_pixel(point, color);
+        }
+        Ok(())
+    }
+}
+
+/// The panel with a `Framebuffer` in front of it, when there's enough memory for one.
+///
+/// Otherwise everything is drawn straight to the panel, which is slower
+/// and flickers on redraws, but keeps the device usable.
+pub struct BufferedPanel<P> {
+    panel: P,
+    buffer: Option<Framebuffer>,
+    /// Drawing outside of it is ignored, the buffer keeps its own copy
+    clip: Rectangle,
+}
+
+impl<P> BufferedPanel<P>
+where
+    P: DrawTarget<Color = Rgb565>,
+{
+    pub fn new(panel: P) -> Self {
+        let bounds = panel.bounding_box();
+        Self {
+            buffer: Framebuffer::try_new(bounds.size),
+            panel,
+            clip: bounds,
+        }
+    }
+
+    pub fn is_buffered(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    pub fn panel_mut(&mut self) -> &mut P {
+        &mut self.panel
+    }
+
+    /// Limits drawing to `area`, e.g. to redraw only part of a screen. `None` resets it.
+    pub fn set_clip(&mut self, area: Option<Rectangle>) {
+        let bounds = self.panel.bounding_box();
+        self.clip = area.map_or(bounds, |area| area.intersection(&bounds));
+        if let Some(buffer) = &mut self.buffer {
+            buffer.set_clip(area);
+        }
+    }
+
+    /// Sends changes to the panel, without a buffer they're already there.
+    pub fn flush(&mut self) -> Result<(), P::Error> {
+        match &mut self.buffer {
+            Some(buffer) => buffer.flush(&mut self.panel),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<P> Dimensions for BufferedPanel<P>
+where
+    P: DrawTarget<Color = Rgb565>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.panel.bounding_box()
+    }
+}
+
+impl<P> DrawTarget for BufferedPanel<P>
+where
+    P: DrawTarget<Color = Rgb565>,
+{
+    type Color = Rgb565;
+    type Error = P::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        match &mut self.buffer {
+            Some(buffer) => {
+                buffer.draw_iter(pixels).ok();
+                Ok(())
+            }
+            None => {
+                let clip = self.clip;
+                self.panel.draw_iter(
+                    pixels
+                        .into_iter()
+                        .filter(|Pixel(point, _)| clip.contains(*point)),
+                )
+            }
+        }
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        match &mut self.buffer {
+            Some(buffer) => {
+                buffer.fill_contiguous(area, colors).ok();
+                Ok(())
+            }
+            // Whole rectangles are sent in a single transfer, instead of pixel by pixel
+            None if self.clip.intersection(area) == *area => {
+                self.panel.fill_contiguous(area, colors)
+            }
+            None => self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            ),
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        match &mut self.buffer {
+            Some(buffer) => {
+                buffer.fill_solid(area, color).ok();
+                Ok(())
+            }
+            None => self.panel.fill_solid(&area.intersection(&self.clip), color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Panel, which remembers what was sent to it
+    struct Panel {
+        size: Size,
+        pixels: Vec<Rgb565>,
+        transfers: Vec<Rectangle>,
+    }
+
+    impl Panel {
+        fn new(size: Size) -> Self {
+            Self {
+                size,
+                pixels: vec![Rgb565::BLACK; (size.width * size.height) as usize],
+                transfers: Vec::new(),
+            }
+        }
+
+        fn pixel(&self, x: u32, y: u32) -> Rgb565 {
+            self.pixels[(y * self.size.width + x) as usize]
+        }
+    }
+
+    impl OriginDimensions for Panel {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    impl DrawTarget for Panel {
+        type Color = Rgb565;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                self.pixels[(point.y as u32 * self.size.width + point.x as u32) as usize] = color;
+            }
+            Ok(())
+        }
+
+        fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Self::Color>,
+        {
+            self.transfers.push(*area);
+            self.draw_iter(area.points().zip(colors).map(|(p, c)| Pixel(p, c)))
+        }
+    }
+
+    fn rectangle(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+        Rectangle::new(Point::new(x, y), Size::new(width, height))
+    }
+
+    /// Flushed buffer, so only what's drawn afterwards is dirty
+    fn clean(size: Size) -> Framebuffer {
+        let mut buffer = Framebuffer::try_new(size).unwrap();
+        buffer.flush(&mut Panel::new(size)).unwrap();
+        assert!(buffer.dirty_rectangles().is_empty());
+        buffer
+    }
+
+    fn touch(buffer: &mut Framebuffer, x: i32, y: i32) {
+        Pixel(Point::new(x, y), Rgb565::WHITE).draw(buffer).unwrap();
+    }
+
+    #[test]
+    fn merges_runs_of_tiles() {
+        let mut buffer = clean(Size::new(64, 32));
+        touch(&mut buffer, 0, 0);
+        touch(&mut buffer, 31, 15);
+        touch(&mut buffer, 50, 3);
+        assert_eq!(
+            buffer.dirty_rectangles(),
+            [rectangle(0, 0, 32, 16), rectangle(48, 0, 16, 16)]
+        );
+    }
+
+    #[test]
+    fn stacks_runs_of_the_same_width() {
+        let mut buffer = clean(Size::new(64, 64));
+        for y in [0, 16] {
+            touch(&mut buffer, 16, y);
+            touch(&mut buffer, 32, y);
+        }
+        // Different width, so it's sent separately
+        touch(&mut buffer, 16, 32);
+        assert_eq!(
+            buffer.dirty_rectangles(),
+            [rectangle(16, 0, 32, 32), rectangle(16, 32, 16, 16)]
+        );
+    }
+
+    #[test]
+    fn clips_edge_tiles_to_the_buffer() {
+        let size = Size::new(135, 240);
+        let buffer = Framebuffer::try_new(size).unwrap();
+        assert_eq!(buffer.dirty_rectangles(), [rectangle(0, 0, 135, 240)]);
+
+        let mut buffer = clean(size);
+        touch(&mut buffer, 134, 239);
+        assert_eq!(buffer.dirty_rectangles(), [rectangle(128, 224, 7, 16)]);
+    }
+
+    #[test]
+    fn flushes_only_changed_tiles() {
+        let size = Size::new(135, 240);
+        let mut buffer = Framebuffer::try_new(size).unwrap();
+        let mut panel = Panel::new(size);
+        buffer.clear(Rgb565::BLUE).unwrap();
+        buffer.flush(&mut panel).unwrap();
+        assert_eq!(panel.transfers, [rectangle(0, 0, 135, 240)]);
+
+        // Redrawing the same contents sends nothing
+        panel.transfers.clear();
+        buffer.clear(Rgb565::BLUE).unwrap();
+        buffer.flush(&mut panel).unwrap();
+        assert!(panel.transfers.is_empty());
+
+        touch(&mut buffer, 20, 40);
+        buffer.flush(&mut panel).unwrap();
+        assert_eq!(panel.transfers, [rectangle(16, 32, 16, 16)]);
+        assert_eq!(panel.pixel(20, 40), Rgb565::WHITE);
+        assert_eq!(panel.pixel(21, 40), Rgb565::BLUE);
+    }
+
+    #[test]
+    fn ignores_drawing_outside_of_clip() {
+        let mut buffer = clean(Size::new(64, 64));
+        buffer.set_clip(Some(rectangle(16, 16, 16, 16)));
+        buffer.clear(Rgb565::RED).unwrap();
+        touch(&mut buffer, 40, 40);
+        assert_eq!(buffer.dirty_rectangles(), [rectangle(16, 16, 16, 16)]);
+
+        buffer.set_clip(None);
+        touch(&mut buffer, 40, 40);
+        assert_eq!(
+            buffer.dirty_rectangles(),
+            [rectangle(16, 16, 16, 16), rectangle(32, 32, 16, 16)]
+        );
+    }
+}
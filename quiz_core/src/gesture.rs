@@ -0,0 +1,260 @@
+use crate::debounce::Edge;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Button {
+    Select,
+    Enter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Press(Button),
+    LongPress(Button),
+    DoublePress(Button),
+    /// Both buttons held down at the same time
+    Chord,
+}
+
+#[derive(Default)]
+struct ButtonState {
+    /// When the button was pressed, if it's still held
+    pressed_at: Option<Duration>,
+    /// When the button was released, if it may still turn into a double press
+    released_at: Option<Duration>,
+    /// Current press is the second one of a double press
+    second_press: bool,
+    /// Current press already produced a gesture, its release should be ignored
+    consumed: bool,
+}
+
+/// Turns debounced button edges into gestures.
+///
+/// A single press is reported only after `double_press_window` passes without a second press,
+/// so call `poll` (at the latest by `next_deadline`) to receive time based gestures.
+/// Like `Debouncer`, it works on plain timestamps and has no hardware dependencies.
+pub struct GestureRecognizer {
+    long_press_time: Duration,
+    double_press_window: Duration,
+    select: ButtonState,
+    enter: ButtonState,
+}
+
+impl GestureRecognizer {
+    pub fn new(long_press_time: Duration, double_press_window: Duration) -> Self {
+        Self {
+            long_press_time,
+            double_press_window,
+            select: ButtonState::default(),
+            enter: ButtonState::default(),
+        }
+    }
+
+    fn state(&mut self, button: Button) -> (&mut ButtonState, &mut ButtonState) {
+        match button {
+            Button::Select => (&mut self.select, &mut self.enter),
+            Button::Enter => (&mut self.enter, &mut self.select),
+        }
+    }
+
+    /// Feeds a debounced edge of `button`, that happened at `now`.
+    pub fn on_edge(&mut self, button: Button, edge: Edge, now: Duration) -> Option<Gesture> {
+        let (state, other) = self.state(button);
+        match edge {
+            Edge::Pressed => {
+                state.pressed_at = Some(now);
+                state.second_press = state.released_at.take().is_some();
+                state.consumed = false;
+                if other.pressed_at.is_some() {
+                    // Chord cancels everything, that was in progress on both buttons
+                    state.consumed = true;
+                    other.consumed = true;
+                    other.released_at = None;
+                    return Some(Gesture::Chord);
+                }
+                None
+            }
+            Edge::Released => {
+                state.pressed_at = None;
+                if state.consumed {
+                    return None;
+                }
+                if state.second_press {
+                    state.second_press = false;
+                    return Some(Gesture::DoublePress(button));
+                }
+                state.released_at = Some(now);
+                None
+            }
+        }
+    }
+
+    /// Returns a gesture, that became certain by `now`.
+    /// Should be called until it returns `None`.
+    pub fn poll(&mut self, now: Duration) -> Option<Gesture> {
+        for button in [Button::Select, Button::Enter] {
+            let long_press_time = self.long_press_time;
+            let double_press_window = self.double_press_window;
+            let (state, _) = self.state(button);
+            if let Some(pressed_at) = state.pressed_at {
+                if !state.consumed && now.saturating_sub(pressed_at) >= long_press_time {
+                    state.consumed = true;
+                    state.second_press = false;
+                    return Some(Gesture::LongPress(button));
+                }
+            }
+            if let Some(released_at) = state.released_at {
+                if now.saturating_sub(released_at) >= double_press_window {
+                    state.released_at = None;
+                    return Some(Gesture::Press(button));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the earliest time, at which `poll` may produce a gesture.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        [&self.select, &self.enter]
+            .into_iter()
+            .flat_map(|state| {
+                let long_press = state
+                    .pressed_at
+                    .filter(|_| !state.consumed)
+                    .map(|pressed_at| pressed_at + self.long_press_time);
+                let press = state
+                    .released_at
+                    .map(|released_at| released_at + self.double_press_window);
+                [long_press, press]
+            })
+            .flatten()
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG_PRESS_TIME: Duration = Duration::from_millis(800);
+    const DOUBLE_PRESS_WINDOW: Duration = Duration::from_millis(250);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn recognizer() -> GestureRecognizer {
+        GestureRecognizer::new(LONG_PRESS_TIME, DOUBLE_PRESS_WINDOW)
+    }
+
+    #[test]
+    fn press_is_reported_after_double_press_window() {
+        let mut gestures = recognizer();
+        assert_eq!(gestures.on_edge(Button::Select, Edge::Pressed, ms(0)), None);
+        assert_eq!(
+            gestures.on_edge(Button::Select, Edge::Released, ms(100)),
+            None
+        );
+        assert_eq!(gestures.next_deadline(), Some(ms(350)));
+        assert_eq!(gestures.poll(ms(349)), None);
+        assert_eq!(gestures.poll(ms(350)), Some(Gesture::Press(Button::Select)));
+        assert_eq!(gestures.poll(ms(351)), None);
+        assert_eq!(gestures.next_deadline(), None);
+    }
+
+    #[test]
+    fn long_press() {
+        let mut gestures = recognizer();
+        gestures.on_edge(Button::Enter, Edge::Pressed, ms(0));
+        assert_eq!(gestures.next_deadline(), Some(LONG_PRESS_TIME));
+        assert_eq!(gestures.poll(ms(799)), None);
+        assert_eq!(
+            gestures.poll(ms(800)),
+            Some(Gesture::LongPress(Button::Enter))
+        );
+        assert_eq!(gestures.next_deadline(), None);
+        // Releasing after a long press doesn't produce another gesture
+        assert_eq!(
+            gestures.on_edge(Button::Enter, Edge::Released, ms(1000)),
+            None
+        );
+        assert_eq!(gestures.poll(ms(2000)), None);
+    }
+
+    #[test]
+    fn double_press() {
+        let mut gestures = recognizer();
+        gestures.on_edge(Button::Select, Edge::Pressed, ms(0));
+        gestures.on_edge(Button::Select, Edge::Released, ms(80));
+        assert_eq!(
+            gestures.on_edge(Button::Select, Edge::Pressed, ms(200)),
+            None
+        );
+        // Pending single press is replaced by the second one
+        assert_eq!(gestures.poll(ms(250)), None);
+        assert_eq!(
+            gestures.on_edge(Button::Select, Edge::Released, ms(280)),
+            Some(Gesture::DoublePress(Button::Select))
+        );
+        assert_eq!(gestures.poll(ms(1000)), None);
+    }
+
+    #[test]
+    fn presses_further_apart_than_window_are_separate() {
+        let mut gestures = recognizer();
+        gestures.on_edge(Button::Select, Edge::Pressed, ms(0));
+        gestures.on_edge(Button::Select, Edge::Released, ms(80));
+        assert_eq!(gestures.poll(ms(330)), Some(Gesture::Press(Button::Select)));
+        gestures.on_edge(Button::Select, Edge::Pressed, ms(400));
+        gestures.on_edge(Button::Select, Edge::Released, ms(480));
+        assert_eq!(gestures.poll(ms(730)), Some(Gesture::Press(Button::Select)));
+    }
+
+    #[test]
+    fn chord_when_both_buttons_are_held() {
+        let mut gestures = recognizer();
+        assert_eq!(gestures.on_edge(Button::Select, Edge::Pressed, ms(0)), None);
+        assert_eq!(
+            gestures.on_edge(Button::Enter, Edge::Pressed, ms(50)),
+            Some(Gesture::Chord)
+        );
+        // Neither the hold nor the releases turn into other gestures
+        assert_eq!(gestures.poll(ms(900)), None);
+        assert_eq!(
+            gestures.on_edge(Button::Select, Edge::Released, ms(1000)),
+            None
+        );
+        assert_eq!(
+            gestures.on_edge(Button::Enter, Edge::Released, ms(1010)),
+            None
+        );
+        assert_eq!(gestures.poll(ms(2000)), None);
+    }
+
+    #[test]
+    fn chord_cancels_pending_press() {
+        let mut gestures = recognizer();
+        gestures.on_edge(Button::Select, Edge::Pressed, ms(0));
+        gestures.on_edge(Button::Enter, Edge::Pressed, ms(100));
+        gestures.on_edge(Button::Enter, Edge::Released, ms(150));
+        assert_eq!(
+            gestures.on_edge(Button::Enter, Edge::Pressed, ms(160)),
+            Some(Gesture::Chord)
+        );
+        assert_eq!(gestures.poll(ms(1000)), None);
+    }
+
+    #[test]
+    fn separate_presses_of_both_buttons() {
+        let mut gestures = recognizer();
+        gestures.on_edge(Button::Select, Edge::Pressed, ms(0));
+        gestures.on_edge(Button::Select, Edge::Released, ms(50));
+        assert_eq!(
+            gestures.on_edge(Button::Enter, Edge::Pressed, ms(100)),
+            None
+        );
+        gestures.on_edge(Button::Enter, Edge::Released, ms(150));
+        assert_eq!(gestures.poll(ms(300)), Some(Gesture::Press(Button::Select)));
+        assert_eq!(gestures.poll(ms(400)), Some(Gesture::Press(Button::Enter)));
+    }
+}
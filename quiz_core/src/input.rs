@@ -0,0 +1,28 @@
+mod scripted;
+
+pub use scripted::ScriptedInput;
+
+use crate::debounce::Edge;
+use crate::gesture::Button;
+use std::time::Duration;
+
+/// Debounced press or release of a logical button.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ButtonEdge {
+    pub button: Button,
+    pub edge: Edge,
+    /// Time since the source was created
+    pub at: Duration,
+}
+
+/// Anything, that can act as quiz buttons: GPIO buttons, touch pads or a recorded script.
+///
+/// Sources are created on the thread, that reads them
+/// (see `controls::spawn_thread` in `mqtt_example`), so they don't have to be `Send`.
+pub trait InputSource {
+    /// Blocks until the next edge, or until `timeout` passes (`None` waits forever).
+    fn next_edge(&mut self, timeout: Option<Duration>) -> Option<ButtonEdge>;
+
+    /// Current time on the same clock, that stamps edges.
+    fn now(&self) -> Duration;
+}
@@ -0,0 +1,147 @@
+use super::{ButtonEdge, InputSource};
+use crate::debounce::Edge;
+use crate::gesture::Button;
+use anyhow::bail;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Replays a timed sequence of button edges, as if they were pressed by a player.
+/// Doesn't touch any hardware, so it can drive the quiz on the host as well.
+pub struct ScriptedInput {
+    script: VecDeque<ButtonEdge>,
+    start: Instant,
+}
+
+impl ScriptedInput {
+    pub fn new(script: impl IntoIterator<Item = ButtonEdge>) -> Self {
+        Self {
+            script: script.into_iter().collect(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Parses a script made of `<ms> <select|enter> <press|release>` entries,
+    /// separated by new lines or `;`, where `ms` is time since start, e.g.
+    /// `0 select press; 100 select release`.
+    pub fn parse(script: &str) -> anyhow::Result<Self> {
+        let mut edges = Vec::new();
+        for entry in script.split([';', '\n']).map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+            let [at, button, edge] = entry.split_whitespace().collect::<Vec<_>>()[..] else {
+                bail!("Invalid script entry `{entry}`");
+            };
+            let at = Duration::from_millis(at.parse()?);
+            let button = match button {
+                "select" => Button::Select,
+                "enter" => Button::Enter,
+                _ => bail!("Unknown button `{button}`"),
+            };
+            let edge = match edge {
+                "press" => Edge::Pressed,
+                "release" => Edge::Released,
+                _ => bail!("Unknown edge `{edge}`"),
+            };
+            edges.push(ButtonEdge { button, edge, at });
+        }
+        edges.sort_by_key(|edge| edge.at);
+        Ok(Self::new(edges))
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn next_edge(&mut self, timeout: Option<Duration>) -> Option<ButtonEdge> {
+        let Some(next) = self.script.front() else {
+            // Script is over, nothing will ever happen again
+            match timeout {
+                Some(timeout) => {
+                    thread::sleep(timeout);
+                    return None;
+                }
+                None => loop {
+                    thread::park();
+                },
+            }
+        };
+        let wait = next.at.saturating_sub(self.now());
+        if let Some(timeout) = timeout.filter(|&timeout| timeout < wait) {
+            thread::sleep(timeout);
+            return None;
+        }
+        thread::sleep(wait);
+        self.script.pop_front()
+    }
+
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(at: u64, button: Button, edge: Edge) -> ButtonEdge {
+        ButtonEdge {
+            button,
+            edge,
+            at: Duration::from_millis(at),
+        }
+    }
+
+    #[test]
+    fn parses_script_in_time_order() {
+        let input =
+            ScriptedInput::parse("100 enter release; 0 enter press\n\n  50 select press ;\n")
+                .unwrap();
+        assert_eq!(
+            Vec::from(input.script),
+            [
+                edge(0, Button::Enter, Edge::Pressed),
+                edge(50, Button::Select, Edge::Pressed),
+                edge(100, Button::Enter, Edge::Released),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_entries() {
+        for script in [
+            "0 select",
+            "0 select press now",
+            "soon select press",
+            "-5 select press",
+            "0 start press",
+            "0 select hold",
+            "0 select press; 10 enter",
+        ] {
+            assert!(ScriptedInput::parse(script).is_err(), "{script}");
+        }
+    }
+
+    #[test]
+    fn edges_wait_for_their_time() {
+        let mut input = ScriptedInput::parse("0 select press; 30 select release").unwrap();
+        assert_eq!(
+            input.next_edge(None),
+            Some(edge(0, Button::Select, Edge::Pressed))
+        );
+        // Timing out before the release doesn't lose it
+        assert_eq!(input.next_edge(Some(Duration::from_millis(5))), None);
+        assert_eq!(
+            input.next_edge(None),
+            Some(edge(30, Button::Select, Edge::Released))
+        );
+        assert!(input.now() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn times_out_after_script_ends() {
+        let mut input = ScriptedInput::parse("").unwrap();
+        let timeout = Duration::from_millis(5);
+        assert_eq!(input.next_edge(Some(timeout)), None);
+        assert!(input.now() >= timeout);
+    }
+}
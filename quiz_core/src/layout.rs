@@ -0,0 +1,109 @@
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+/// Height of the status bar at the top of every screen
+const STATUS_BAR_HEIGHT: u32 = 20;
+/// Height of the progress header above a question
+const PROGRESS_HEIGHT: u32 = 16;
+/// Height of a single answer option, unless the screen is too small for it
+const OPTION_HEIGHT: u32 = 40;
+/// Number of option slots, questions with more options are rejected
+pub const MAX_OPTIONS: usize = 4;
+
+/// Areas of the screen, that `QuizRenderer` draws into.
+/// Portrait screens list options one under another,
+/// landscape ones put them in a 2x2 grid, to leave some room for the question.
+pub struct ScreenLayout {
+    pub status_bar: Rectangle,
+    pub question: Rectangle,
+    /// Whole area below the status bar, used for messages and diagnostics
+    pub content: Rectangle,
+    options: Rectangle,
+    columns: u32,
+    option_height: u32,
+}
+
+impl ScreenLayout {
+    pub fn new(size: Size) -> Self {
+        let columns = if size.width > size.height { 2 } else { 1 };
+        let rows = MAX_OPTIONS as u32 / columns;
+        // Options may take up to 3/4 of the space below the status bar
+        let option_height =
+            OPTION_HEIGHT.min(size.height.saturating_sub(STATUS_BAR_HEIGHT) * 3 / 4 / rows);
+        let options_height = option_height * rows;
+        let status_bar = Rectangle::new(Point::zero(), Size::new(size.width, STATUS_BAR_HEIGHT));
+        let content = Rectangle::new(
+            Point::new(0, STATUS_BAR_HEIGHT as i32),
+            Size::new(size.width, size.height.saturating_sub(STATUS_BAR_HEIGHT)),
+        );
+        let question = Rectangle::new(
+            content.top_left,
+            Size::new(
+                size.width,
+                content.size.height.saturating_sub(options_height),
+            ),
+        );
+        let options = Rectangle::new(
+            Point::new(0, size.height.saturating_sub(options_height) as i32),
+            Size::new(size.width, options_height),
+        );
+        Self {
+            status_bar,
+            question,
+            content,
+            options,
+            columns,
+            option_height,
+        }
+    }
+
+    /// Splits the question area into a progress header and the rest for the question itself.
+    pub fn split_progress(&self) -> (Rectangle, Rectangle) {
+        let height = PROGRESS_HEIGHT.min(self.question.size.height);
+        let progress = Rectangle::new(
+            self.question.top_left,
+            Size::new(self.question.size.width, height),
+        );
+        let question = Rectangle::new(
+            self.question.top_left + Point::new(0, height as i32),
+            Size::new(self.question.size.width, self.question.size.height - height),
+        );
+        (progress, question)
+    }
+
+    /// Splits the content area into a logo of `logo` size and the rest for text,
+    /// next to it on landscape screens, under it on portrait ones.
+    /// The logo area takes at most half of the screen, so a bigger logo has to be scaled down.
+    pub fn split_idle(&self, logo: Size) -> (Rectangle, Rectangle) {
+        let content = self.content;
+        if self.columns > 1 {
+            let width = logo.width.min(content.size.width / 2);
+            let logo = Rectangle::new(content.top_left, Size::new(width, content.size.height));
+            let text = Rectangle::new(
+                content.top_left + Point::new(width as i32, 0),
+                Size::new(content.size.width - width, content.size.height),
+            );
+            (logo, text)
+        } else {
+            let height = logo.height.min(content.size.height / 2);
+            let logo = Rectangle::new(content.top_left, Size::new(content.size.width, height));
+            let text = Rectangle::new(
+                content.top_left + Point::new(0, height as i32),
+                Size::new(content.size.width, content.size.height - height),
+            );
+            (logo, text)
+        }
+    }
+
+    /// Area of the option at `idx`, filled row by row.
+    pub fn option(&self, idx: usize) -> Rectangle {
+        let idx = idx as u32;
+        let width = self.options.size.width / self.columns;
+        let (column, row) = (idx % self.columns, idx / self.columns);
+        Rectangle::new(
+            self.options.top_left
+                + Point::new((column * width) as i32, (row * self.option_height) as i32),
+            Size::new(width, self.option_height),
+        )
+    }
+}
@@ -0,0 +1,25 @@
+//! Parts of the quiz device which do not depend on ESP-IDF (screens, input,
+//! signal processing and the event recorder), so they can be built and tested on the host.
+
+pub mod animation;
+pub mod confetti;
+pub mod config;
+pub mod debounce;
+pub mod diagnostics;
+pub mod event;
+pub mod filter;
+pub mod framebuffer;
+pub mod gesture;
+pub mod input;
+pub mod layout;
+pub mod markup;
+pub mod picture;
+pub mod qr;
+pub mod quadrature;
+pub mod quiz;
+pub mod recorder;
+pub mod renderer;
+pub mod status_bar;
+pub mod text_layout;
+pub mod theme;
+pub mod wipe;
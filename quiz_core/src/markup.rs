@@ -0,0 +1,605 @@
+//! Lightweight markup for `message` payloads:
+//!
+//! - `*bold*`
+//! - `[red]colored[/]`, with `red`, `green`, `blue`, `yellow`, `accent` or `warning`
+//! - `\n` (or an actual new line) breaks the line
+//! - `^` at the start of a line centers it
+//! - `:check:`, `:cross:`, `:star:`, `:heart:` and `:pizza:` icons
+//! - `\` escapes the next character, e.g. `\*`
+//!
+//! Anything that isn't valid markup is shown as is, e.g. `[unknown]`, `12:30:00` or `2*3=6`.
+
+use crate::text_layout::{Font, TextLayout};
+use crate::theme::Theme;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{
+    Circle, Line, Polyline, PrimitiveStyle, Rectangle, StyledDrawable, Triangle,
+};
+use embedded_graphics::text::{Baseline, Text};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Icon {
+    Check,
+    Cross,
+    Star,
+    Heart,
+    Pizza,
+}
+
+impl Icon {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "check" => Icon::Check,
+            "cross" => Icon::Cross,
+            "star" => Icon::Star,
+            "heart" => Icon::Heart,
+            "pizza" => Icon::Pizza,
+            _ => return None,
+        })
+    }
+}
+
+/// Color of a span, either fixed or taken from the theme.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Highlight {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Accent,
+    Warning,
+}
+
+impl Highlight {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "red" => Highlight::Red,
+            "green" => Highlight::Green,
+            "blue" => Highlight::Blue,
+            "yellow" => Highlight::Yellow,
+            "accent" => Highlight::Accent,
+            "warning" => Highlight::Warning,
+            _ => return None,
+        })
+    }
+
+    fn color(self, theme: &Theme) -> Rgb565 {
+        match self {
+            Highlight::Red => Rgb565::RED,
+            Highlight::Green => Rgb565::GREEN,
+            Highlight::Blue => Rgb565::BLUE,
+            Highlight::Yellow => Rgb565::YELLOW,
+            Highlight::Accent => theme.accent,
+            Highlight::Warning => theme.warning,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    /// Theme's text color, when not set
+    pub color: Option<Highlight>,
+}
+
+impl Style {
+    fn color(self, theme: &Theme) -> Rgb565 {
+        self.color.map_or(theme.text, |color| color.color(theme))
+    }
+}
+
+pub enum Content {
+    Text(String),
+    Icon(Icon),
+}
+
+/// Piece of a line drawn with a single style
+pub struct Run {
+    pub style: Style,
+    pub content: Content,
+}
+
+#[derive(Default)]
+pub struct MarkupLine {
+    pub centered: bool,
+    pub runs: Vec<Run>,
+}
+
+impl MarkupLine {
+    fn push_char(&mut self, c: char, style: Style) {
+        match self.runs.last_mut() {
+            Some(Run {
+                style: last_style,
+                content: Content::Text(text),
+            }) if *last_style == style => text.push(c),
+            _ => self.runs.push(Run {
+                style,
+                content: Content::Text(String::from(c)),
+            }),
+        }
+    }
+}
+
+/// Parsed message, a list of lines made of styled runs.
+pub struct Markup {
+    pub lines: Vec<MarkupLine>,
+}
+
+impl Markup {
+    /// Never fails, invalid markup is kept as plain text.
+    pub fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut line = MarkupLine::default();
+        let mut style = Style::default();
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+            match c {
+                '^' if line.runs.is_empty() && !line.centered => line.centered = true,
+                '\n' => lines.push(std::mem::take(&mut line)),
+                '\\' => match rest.chars().next() {
+                    Some('n') => {
+                        rest = &rest[1..];
+                        lines.push(std::mem::take(&mut line));
+                    }
+                    Some(escaped) => {
+                        rest = &rest[escaped.len_utf8()..];
+                        line.push_char(escaped, style);
+                    }
+                    None => line.push_char(c, style),
+                },
+                // A lone asterisk, e.g. in `2*3=6`, is kept as is
+                '*' if style.bold || tag(rest, '*').is_some() => style.bold = !style.bold,
+                '[' => match tag(rest, ']') {
+                    Some(("/", after)) => {
+                        style.color = None;
+                        rest = after;
+                    }
+                    Some((name, after)) if Highlight::from_name(name).is_some() => {
+                        style.color = Highlight::from_name(name);
+                        rest = after;
+                    }
+                    _ => line.push_char(c, style),
+                },
+                ':' => match tag(rest, ':') {
+                    Some((name, after)) if Icon::from_name(name).is_some() => {
+                        line.runs.push(Run {
+                            style,
+                            content: Content::Icon(Icon::from_name(name).unwrap()),
+                        });
+                        rest = after;
+                    }
+                    _ => line.push_char(c, style),
+                },
+                _ => line.push_char(c, style),
+            }
+        }
+        lines.push(line);
+        Self { lines }
+    }
+}
+
+/// Splits `text` at the first unescaped `end`, returning what's before and after it.
+/// Tags never span lines.
+fn tag(text: &str, end: char) -> Option<(&str, &str)> {
+    let mut chars = text.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\n' => return None,
+            '\\' => {
+                if let Some((_, 'n')) = chars.next() {
+                    return None;
+                }
+            }
+            _ if c == end => return Some((&text[..idx], &text[idx + c.len_utf8()..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Glyph {
+    Char(char),
+    Icon(Icon),
+}
+
+/// Single character or icon of a wrapped row
+#[derive(Clone, Copy)]
+struct Cell {
+    glyph: Glyph,
+    style: Style,
+}
+
+impl Cell {
+    /// In columns, icons are twice as wide as characters, so they stay roughly square.
+    fn width(&self) -> usize {
+        match self.glyph {
+            Glyph::Char(_) => 1,
+            Glyph::Icon(_) => 2,
+        }
+    }
+}
+
+struct Row {
+    centered: bool,
+    cells: Vec<Cell>,
+}
+
+/// `Markup` broken into rows and pages like `TextLayout` does with plain text.
+pub struct MarkupLayout {
+    pub font: Font,
+    pages: Vec<Vec<Row>>,
+}
+
+impl MarkupLayout {
+    pub fn fit(markup: &Markup, size: Size) -> Self {
+        for font in Font::ALL {
+            let (columns, rows) = TextLayout::capacity(font, size);
+            let wrapped = wrap(markup, columns);
+            if wrapped.len() <= rows {
+                return Self {
+                    font,
+                    pages: vec![wrapped],
+                };
+            }
+        }
+        let font = Font::Small;
+        let (columns, rows) = TextLayout::capacity(font, size);
+        let mut pages = Vec::new();
+        let mut wrapped = wrap(markup, columns).into_iter().peekable();
+        while wrapped.peek().is_some() {
+            pages.push(wrapped.by_ref().take(rows.max(1)).collect());
+        }
+        Self { font, pages }
+    }
+
+    /// Wraps around after the last page, like `TextLayout::page`.
+    fn page(&self, page: usize) -> &[Row] {
+        &self.pages[page % self.pages.len()]
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+/// Breaks lines into rows of at most `columns` wide, the same way as `text_layout::wrap`.
+fn wrap(markup: &Markup, columns: usize) -> Vec<Row> {
+    let columns = columns.max(2);
+    let mut rows = Vec::new();
+    for line in &markup.lines {
+        let mut cells = Vec::new();
+        for run in &line.runs {
+            match &run.content {
+                Content::Text(text) => cells.extend(text.chars().map(|c| Cell {
+                    glyph: Glyph::Char(c),
+                    style: run.style,
+                })),
+                Content::Icon(icon) => cells.push(Cell {
+                    glyph: Glyph::Icon(*icon),
+                    style: run.style,
+                }),
+            }
+        }
+        let mut row = Vec::new();
+        let mut row_width = 0;
+        for word in cells
+            .split(|cell| cell.glyph == Glyph::Char(' '))
+            .filter(|word| !word.is_empty())
+        {
+            let word_width: usize = word.iter().map(Cell::width).sum();
+            if row_width > 0 && row_width + 1 + word_width > columns {
+                rows.push(Row {
+                    centered: line.centered,
+                    cells: std::mem::take(&mut row),
+                });
+                row_width = 0;
+            }
+            if row_width > 0 {
+                row.push(Cell {
+                    glyph: Glyph::Char(' '),
+                    style: Style::default(),
+                });
+                row_width += 1;
+            }
+            for cell in word {
+                if row_width + cell.width() > columns {
+                    rows.push(Row {
+                        centered: line.centered,
+                        cells: std::mem::take(&mut row),
+                    });
+                    row_width = 0;
+                }
+                row.push(*cell);
+                row_width += cell.width();
+            }
+        }
+        rows.push(Row {
+            centered: line.centered,
+            cells: row,
+        });
+    }
+    rows
+}
+
+/// Page of a `MarkupLayout` in `area`, centered vertically.
+pub struct MarkupView<'a> {
+    layout: &'a MarkupLayout,
+    area: Rectangle,
+    page: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> MarkupView<'a> {
+    pub fn new(layout: &'a MarkupLayout, area: Rectangle, page: usize, theme: &'a Theme) -> Self {
+        Self {
+            layout,
+            area,
+            page,
+            theme,
+        }
+    }
+}
+
+impl Drawable for MarkupView<'_> {
+    type Color = Rgb565;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let font = self.layout.font;
+        let char_size = font.char_size();
+        let rows = self.layout.page(self.page);
+        let y_offset = self
+            .area
+            .size
+            .height
+            .saturating_sub(rows.len() as u32 * char_size.height)
+            / 2;
+        for (idx, row) in rows.iter().enumerate() {
+            let width = row.cells.iter().map(Cell::width).sum::<usize>() as u32 * char_size.width;
+            let x_offset = match row.centered {
+                true => self.area.size.width.saturating_sub(width) / 2,
+                false => 0,
+            };
+            let mut position = self.area.top_left
+                + Point::new(
+                    x_offset as i32,
+                    (y_offset + idx as u32 * char_size.height) as i32,
+                );
+            for run in row.cells.chunk_by(|a, b| {
+                matches!((a.glyph, b.glyph), (Glyph::Char(_), Glyph::Char(_))) && a.style == b.style
+            }) {
+                let style = run[0].style;
+                let color = style.color(self.theme);
+                match run[0].glyph {
+                    Glyph::Icon(icon) => {
+                        let cell = Rectangle::new(
+                            position,
+                            Size::new(2 * char_size.width, char_size.height),
+                        );
+                        draw_icon(target, icon, cell, color, self.theme)?;
+                        position.x += cell.size.width as i32;
+                    }
+                    Glyph::Char(_) => {
+                        let text: String = run
+                            .iter()
+                            .filter_map(|cell| match cell.glyph {
+                                Glyph::Char(c) => Some(c),
+                                Glyph::Icon(_) => None,
+                            })
+                            .collect();
+                        // Bold is faked by drawing the text again, a pixel to the right
+                        let passes = if style.bold { 2 } else { 1 };
+                        for pass in 0..passes {
+                            Text::with_baseline(
+                                &text,
+                                position + Point::new(pass, 0),
+                                font.style(color),
+                                Baseline::Top,
+                            )
+                            .draw(target)?;
+                        }
+                        position.x += (run.len() as u32 * char_size.width) as i32;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws `icon` as big as fits into `cell`, centered in it.
+fn draw_icon<D>(
+    target: &mut D,
+    icon: Icon,
+    cell: Rectangle,
+    color: Rgb565,
+    theme: &Theme,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let side = cell.size.width.min(cell.size.height).saturating_sub(2) as i32;
+    let origin = cell.center() - Point::new_equal(side / 2);
+    // Points are given in 1/10 of the icon's side
+    let at = |x: i32, y: i32| origin + Point::new(x * side / 10, y * side / 10);
+    let stroke = PrimitiveStyle::with_stroke(color, 2);
+    let fill = PrimitiveStyle::with_fill(color);
+    match icon {
+        Icon::Check => Polyline::new(&[at(1, 5), at(4, 8), at(9, 2)]).draw_styled(&stroke, target),
+        Icon::Cross => {
+            Line::new(at(1, 1), at(9, 9)).draw_styled(&stroke, target)?;
+            Line::new(at(1, 9), at(9, 1)).draw_styled(&stroke, target)
+        }
+        Icon::Star => {
+            let radius = side as f32 / 2.0;
+            let points: Vec<Point> = (0..=10)
+                .map(|idx| {
+                    let length = if idx % 2 == 0 { radius } else { radius * 0.4 };
+                    let angle = idx as f32 * std::f32::consts::PI / 5.0;
+                    cell.center()
+                        + Point::new(
+                            (length * angle.sin()) as i32,
+                            -(length * angle.cos()) as i32,
+                        )
+                })
+                .collect();
+            Polyline::new(&points).draw_styled(&PrimitiveStyle::with_stroke(color, 1), target)
+        }
+        Icon::Heart => {
+            let diameter = (side / 2 + 1) as u32;
+            Circle::new(at(0, 0), diameter).draw_styled(&fill, target)?;
+            Circle::new(at(10, 0) - Point::new(diameter as i32, 0), diameter)
+                .draw_styled(&fill, target)?;
+            Triangle::new(at(0, 3), at(10, 3), at(5, 10)).draw_styled(&fill, target)
+        }
+        Icon::Pizza => {
+            Triangle::new(at(0, 1), at(10, 1), at(5, 10)).draw_styled(&fill, target)?;
+            Line::new(at(0, 1), at(10, 1)).draw_styled(&stroke, target)?;
+            let pepperoni = PrimitiveStyle::with_fill(theme.background);
+            let diameter = (side / 5).max(1) as u32;
+            Circle::with_center(at(4, 3), diameter).draw_styled(&pepperoni, target)?;
+            Circle::with_center(at(6, 5), diameter).draw_styled(&pepperoni, target)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: Style = Style {
+        bold: false,
+        color: None,
+    };
+    const BOLD: Style = Style {
+        bold: true,
+        color: None,
+    };
+
+    /// Runs of every line, with icons written as `:Name:`
+    fn parse(text: &str) -> Vec<(bool, Vec<(Style, String)>)> {
+        Markup::parse(text)
+            .lines
+            .iter()
+            .map(|line| {
+                let runs = line
+                    .runs
+                    .iter()
+                    .map(|run| match &run.content {
+                        Content::Text(text) => (run.style, text.clone()),
+                        Content::Icon(icon) => (run.style, format!(":{icon:?}:")),
+                    })
+                    .collect();
+                (line.centered, runs)
+            })
+            .collect()
+    }
+
+    fn plain(text: &str) -> Vec<(bool, Vec<(Style, String)>)> {
+        vec![(false, vec![(PLAIN, String::from(text))])]
+    }
+
+    #[test]
+    fn bold() {
+        assert_eq!(
+            parse("a *b* c"),
+            [(
+                false,
+                vec![
+                    (PLAIN, String::from("a ")),
+                    (BOLD, String::from("b")),
+                    (PLAIN, String::from(" c")),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn lone_asterisks_are_literal() {
+        assert_eq!(parse("2*3=6"), plain("2*3=6"));
+        assert_eq!(
+            parse("*a\\nb*"),
+            [
+                (false, vec![(PLAIN, String::from("*a"))]),
+                (false, vec![(PLAIN, String::from("b*"))])
+            ]
+        );
+        assert_eq!(parse("*a\\*"), plain("*a*"));
+    }
+
+    #[test]
+    fn colors() {
+        let red = Style {
+            bold: false,
+            color: Some(Highlight::Red),
+        };
+        assert_eq!(
+            parse("[red]a[/]b"),
+            [(
+                false,
+                vec![(red, String::from("a")), (PLAIN, String::from("b"))]
+            )]
+        );
+    }
+
+    #[test]
+    fn unbalanced_color_lasts_until_the_end() {
+        let green = Style {
+            bold: false,
+            color: Some(Highlight::Green),
+        };
+        assert_eq!(
+            parse("[green]a"),
+            [(false, vec![(green, String::from("a"))])]
+        );
+        assert_eq!(parse("a[/]b"), plain("ab"));
+    }
+
+    #[test]
+    fn unknown_tags_are_literal() {
+        assert_eq!(parse("[pink]a[/"), plain("[pink]a[/"));
+        assert_eq!(parse(":unknown: :check"), plain(":unknown: :check"));
+    }
+
+    #[test]
+    fn times_are_literal() {
+        assert_eq!(parse("at 12:30:00"), plain("at 12:30:00"));
+    }
+
+    #[test]
+    fn icons() {
+        assert_eq!(
+            parse(":star:x"),
+            [(
+                false,
+                vec![(PLAIN, String::from(":Star:")), (PLAIN, String::from("x"))]
+            )]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(parse("\\*a\\* \\[red] \\\\"), plain("*a* [red] \\"));
+        assert_eq!(parse("a\\"), plain("a\\"));
+    }
+
+    #[test]
+    fn line_breaks() {
+        let lines = parse("a\\nb\nc");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], plain("c")[0]);
+    }
+
+    #[test]
+    fn centering() {
+        assert_eq!(parse("^a"), [(true, vec![(PLAIN, String::from("a"))])]);
+        assert_eq!(parse("a^b"), plain("a^b"));
+        assert_eq!(parse("^^a"), [(true, vec![(PLAIN, String::from("^a"))])]);
+    }
+}
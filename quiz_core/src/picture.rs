@@ -0,0 +1,139 @@
+use crate::config::MAX_IMAGE_SIZE;
+use anyhow::{anyhow, bail};
+use embedded_graphics::image::Image;
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use tinybmp::Bmp;
+use tinyqoi::Qoi;
+
+/// Image received over MQTT, decoded straight from the payload, without copying pixels.
+pub enum Picture<'a> {
+    Bmp(Bmp<'a, Rgb565>),
+    Qoi(Qoi<'a>),
+}
+
+impl<'a> Picture<'a> {
+    /// Recognizes the format by its magic bytes and validates the header.
+    pub fn parse(data: &'a [u8]) -> anyhow::Result<Self> {
+        let picture = if data.starts_with(b"BM") {
+            Picture::Bmp(Bmp::from_slice(data).map_err(|e| anyhow!("Invalid BMP: {e:?}"))?)
+        } else if data.starts_with(b"qoif") {
+            Picture::Qoi(Qoi::new(data).map_err(|e| anyhow!("Invalid QOI: {e:?}"))?)
+        } else {
+            bail!("Unknown image format, expected BMP or QOI");
+        };
+        let size = picture.size();
+        if size.width > MAX_IMAGE_SIZE.0 || size.height > MAX_IMAGE_SIZE.1 {
+            bail!("Image too big: {}x{}", size.width, size.height);
+        }
+        Ok(picture)
+    }
+
+    pub fn size(&self) -> Size {
+        match self {
+            Picture::Bmp(bmp) => bmp.size(),
+            Picture::Qoi(qoi) => qoi.size(),
+        }
+    }
+
+    /// Draws the picture centered in `area`, cropping whatever doesn't fit.
+    pub fn draw_centered<D>(&self, target: &mut D, area: Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let size = self.size();
+        let position = area.top_left
+            + Point::new(
+                (area.size.width as i32 - size.width as i32) / 2,
+                (area.size.height as i32 - size.height as i32) / 2,
+            );
+        self.draw_at(&mut target.clipped(&area), position)
+    }
+
+    /// Draws the picture centered in `area`, scaled down to fit it, keeping the aspect ratio.
+    /// Pictures that fit are drawn as they are, they're never scaled up.
+    pub fn draw_fitted<D>(&self, target: &mut D, area: Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let size = self.size();
+        // Scale by `numerator / denominator` of the dimension, that's tighter
+        let (numerator, denominator) =
+            if area.size.width * size.height <= area.size.height * size.width {
+                (area.size.width, size.width)
+            } else {
+                (area.size.height, size.height)
+            };
+        if numerator >= denominator {
+            return self.draw_centered(target, area);
+        }
+        let scaled = size * numerator / denominator;
+        let offset = area.top_left
+            + Point::new(
+                (area.size.width - scaled.width) as i32 / 2,
+                (area.size.height - scaled.height) as i32 / 2,
+            );
+        self.draw_at(
+            &mut Scaled {
+                target: &mut target.clipped(&area),
+                offset,
+                numerator,
+                denominator,
+            },
+            Point::zero(),
+        )
+    }
+
+    fn draw_at<D>(&self, target: &mut D, position: Point) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        match self {
+            Picture::Bmp(bmp) => Image::new(bmp, position).draw(target),
+            // QOI is always decoded to 24 bit colors
+            Picture::Qoi(qoi) => {
+                Image::new(qoi, position).draw(&mut target.color_converted::<Rgb888>())
+            }
+        }
+    }
+}
+
+/// Scales everything drawn into it down by `numerator / denominator` (nearest neighbour)
+/// and moves it by `offset`.
+struct Scaled<'a, D> {
+    target: &'a mut D,
+    offset: Point,
+    numerator: u32,
+    denominator: u32,
+}
+
+impl<D> Dimensions for Scaled<'_, D>
+where
+    D: DrawTarget,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Scaled<'_, D>
+where
+    D: DrawTarget,
+{
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (numerator, denominator) = (self.numerator as i32, self.denominator as i32);
+        let offset = self.offset;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(offset + point * numerator / denominator, color)),
+        )
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::bail;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use qrcodegen::{QrCode, QrCodeEcc};
+
+/// Empty margin around the code in modules, as required by the spec
+const QUIET_ZONE: i32 = 4;
+/// Modules smaller than that (in pixels) can't be scanned reliably
+const MIN_SCALE: u32 = 2;
+
+/// Encodes `text`, rejecting codes, which can't be drawn scannable into `area`.
+pub fn encode(text: &str, area: Size) -> anyhow::Result<QrCode> {
+    let code = QrCode::encode_text(text, QrCodeEcc::Medium)?;
+    if scale(&code, area) < MIN_SCALE {
+        bail!(
+            "{} characters need {}x{} modules, too many for the screen",
+            text.chars().count(),
+            code.size(),
+            code.size()
+        );
+    }
+    Ok(code)
+}
+
+/// Pixels per module, so the code with its quiet zone fits into `area`
+fn scale(code: &QrCode, area: Size) -> u32 {
+    let modules = (code.size() + 2 * QUIET_ZONE) as u32;
+    area.width.min(area.height) / modules
+}
+
+/// QR code scaled by a whole number of pixels per module, as big as fits into `area`,
+/// and centered in it.
+///
+/// Always dark on light, regardless of the theme, as some scanners can't read inverted codes.
+pub struct QrCodeView<'a> {
+    code: &'a QrCode,
+    area: Rectangle,
+}
+
+impl<'a> QrCodeView<'a> {
+    pub fn new(code: &'a QrCode, area: Rectangle) -> Self {
+        Self { code, area }
+    }
+}
+
+impl Drawable for QrCodeView<'_> {
+    type Color = Rgb565;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let modules = (self.code.size() + 2 * QUIET_ZONE) as u32;
+        let scale = scale(self.code, self.area.size).max(1);
+        let side = modules * scale;
+        let top_left = self.area.top_left
+            + Point::new(
+                self.area.size.width.saturating_sub(side) as i32 / 2,
+                self.area.size.height.saturating_sub(side) as i32 / 2,
+            );
+        target.fill_solid(
+            &Rectangle::new(top_left, Size::new_equal(side)),
+            Rgb565::WHITE,
+        )?;
+        for y in 0..self.code.size() {
+            for x in 0..self.code.size() {
+                if self.code.get_module(x, y) {
+                    let position = Point::new(x + QUIET_ZONE, y + QUIET_ZONE) * scale as i32;
+                    target.fill_solid(
+                        &Rectangle::new(top_left + position, Size::new_equal(scale)),
+                        Rgb565::BLACK,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Content area of the T-Display in portrait
+    const AREA: Size = Size::new(135, 215);
+
+    #[test]
+    fn encodes_short_text() {
+        let code = encode("https://example.com/quiz", AREA).unwrap();
+        assert!(scale(&code, AREA) >= MIN_SCALE);
+    }
+
+    #[test]
+    fn rejects_codes_too_big_to_scan() {
+        let text = "a".repeat(1000);
+        assert!(encode(&text, AREA).is_err());
+        // Fits a bigger screen
+        assert!(encode(&text, Size::new(320, 296)).is_ok());
+    }
+
+    #[test]
+    fn rejects_text_too_long_for_any_code() {
+        assert!(encode(&"a".repeat(5000), Size::new(1000, 1000)).is_err());
+    }
+}
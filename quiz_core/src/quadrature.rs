@@ -0,0 +1,123 @@
+/// Turns readings of a wrapping quadrature counter into whole detent steps.
+///
+/// PCNT does the actual quadrature decoding in hardware and counts every edge of both
+/// encoder channels, so one detent is usually a few counts, and the counter
+/// jumps back to 0, when it reaches `±limit`.
+pub struct DetentDecoder {
+    counts_per_detent: i32,
+    limit: i32,
+    last_count: i32,
+    /// Counts, that don't make a whole detent yet
+    remainder: i32,
+}
+
+impl DetentDecoder {
+    pub const fn new(counts_per_detent: i16, limit: i16) -> Self {
+        Self {
+            counts_per_detent: counts_per_detent as i32,
+            limit: limit as i32,
+            last_count: 0,
+            remainder: 0,
+        }
+    }
+
+    /// Feeds a new counter reading and returns the number of detents turned since
+    /// the previous one, positive for clockwise rotation.
+    /// Readings have to be frequent enough, that the counter moves less than `limit / 2`.
+    /// Readings beyond `±limit` can't come from the counter and are ignored.
+    pub fn update(&mut self, count: i16) -> i32 {
+        let count = count as i32;
+        if count.abs() > self.limit {
+            return 0;
+        }
+        let mut delta = count - self.last_count;
+        self.last_count = count;
+        // A big jump means the counter wrapped around one of the limits
+        if delta > self.limit / 2 {
+            delta -= self.limit;
+        } else if delta < -self.limit / 2 {
+            delta += self.limit;
+        }
+
+        self.remainder += delta;
+        let detents = self.remainder / self.counts_per_detent;
+        self.remainder %= self.counts_per_detent;
+        detents
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder() -> DetentDecoder {
+        DetentDecoder::new(4, 1000)
+    }
+
+    #[test]
+    fn whole_detents() {
+        let mut decoder = decoder();
+        assert_eq!(decoder.update(4), 1);
+        assert_eq!(decoder.update(12), 2);
+        assert_eq!(decoder.update(8), -1);
+    }
+
+    #[test]
+    fn partial_detents_add_up() {
+        let mut decoder = decoder();
+        assert_eq!(decoder.update(1), 0);
+        assert_eq!(decoder.update(3), 0);
+        assert_eq!(decoder.update(5), 1);
+        assert_eq!(decoder.update(7), 0);
+        assert_eq!(decoder.update(8), 1);
+    }
+
+    #[test]
+    fn direction_reversal_cancels_partial_detent() {
+        let mut decoder = decoder();
+        assert_eq!(decoder.update(3), 0);
+        assert_eq!(decoder.update(0), 0);
+        assert_eq!(decoder.update(-3), 0);
+        assert_eq!(decoder.update(-4), -1);
+        assert_eq!(decoder.update(0), 1);
+    }
+
+    /// Feeds readings from the current count to `to` in steps of `step`,
+    /// returning the sum of detents
+    fn turn(decoder: &mut DetentDecoder, to: i16, step: i16) -> i32 {
+        let mut detents = 0;
+        let mut count = decoder.last_count as i16;
+        while count != to {
+            count += step;
+            detents += decoder.update(count);
+        }
+        detents
+    }
+
+    #[test]
+    fn wraps_around_limits() {
+        let mut decoder = decoder();
+        assert_eq!(turn(&mut decoder, 996, 4), 249);
+        // Counter reached 1000 and restarted from 0
+        assert_eq!(decoder.update(4), 2);
+        assert_eq!(turn(&mut decoder, -996, -4), -250);
+        // Counter reached -1000 and restarted from 0
+        assert_eq!(decoder.update(-4), -2);
+    }
+
+    #[test]
+    fn big_jump_is_read_as_wrap() {
+        let mut decoder = decoder();
+        // Readings are too far apart, so it looks like a backward wrap from 0
+        assert_eq!(decoder.update(996), -1);
+    }
+
+    #[test]
+    fn ignores_invalid_readings() {
+        let mut decoder = decoder();
+        decoder.update(8);
+        assert_eq!(decoder.update(i16::MAX), 0);
+        assert_eq!(decoder.update(-1001), 0);
+        assert_eq!(decoder.update(12), 1);
+    }
+}
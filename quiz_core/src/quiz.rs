@@ -0,0 +1,144 @@
+use crate::layout::MAX_OPTIONS;
+
+/// Question being answered, together with the player's cursor over its options.
+pub struct Question {
+    pub id: String,
+    pub text: String,
+    pub options: Vec<String>,
+    pub progress: Option<Progress>,
+    selection: usize,
+}
+
+/// Position of a question in the quiz, e.g. 3rd of 10.
+pub struct Progress {
+    /// Counted from 1
+    pub index: u32,
+    pub total: u32,
+    pub category: Option<String>,
+}
+
+impl Question {
+    /// Parses `id|text|option|option|...` payload.
+    /// The id may be followed by progress: `id;index/total|...` or `id;index/total;category|...`.
+    /// Returns `None`, if the payload has no options or more than fit on the screen.
+    pub fn from_payload(payload: &str) -> Option<Self> {
+        let mut parts = payload.split('|').map(String::from);
+        let header = parts.next()?;
+        let (id, progress) = match header.split_once(';') {
+            Some((id, progress)) => (String::from(id), Progress::parse(progress)),
+            None => (header, None),
+        };
+        let text = parts.next()?;
+        let options: Vec<_> = parts.collect();
+        if options.is_empty() || options.len() > MAX_OPTIONS {
+            return None;
+        }
+        Some(Self {
+            id,
+            text,
+            options,
+            progress,
+            selection: 0,
+        })
+    }
+
+    pub fn selection(&self) -> usize {
+        self.selection
+    }
+
+    pub fn select_next(&mut self) {
+        self.selection = (self.selection + 1) % self.options.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selection = (self.selection + self.options.len() - 1) % self.options.len();
+    }
+}
+
+impl Progress {
+    /// Parses `index/total` or `index/total;category`, rejecting inconsistent numbers.
+    fn parse(progress: &str) -> Option<Self> {
+        let (counts, category) = match progress.split_once(';') {
+            Some((counts, category)) => (counts, Some(String::from(category))),
+            None => (progress, None),
+        };
+        let (index, total) = counts.split_once('/')?;
+        let (index, total) = (index.trim().parse().ok()?, total.trim().parse().ok()?);
+        if index == 0 || index > total {
+            return None;
+        }
+        Some(Self {
+            index,
+            total,
+            category: category.filter(|category| !category.is_empty()),
+        })
+    }
+}
+
+/// Outcome of an answer, revealed by the quiz master.
+pub struct AnswerResult {
+    pub device_id: String,
+    pub question_id: String,
+    pub correct: usize,
+    pub points: i32,
+}
+
+impl AnswerResult {
+    /// Parses `device|question id|correct option|points` payload.
+    pub fn from_payload(payload: &str) -> Option<Self> {
+        let mut parts = payload.split('|');
+        let device_id = String::from(parts.next()?);
+        let question_id = String::from(parts.next()?);
+        let correct = parts.next()?.parse().ok()?;
+        let points = parts.next()?.parse().ok()?;
+        Some(Self {
+            device_id,
+            question_id,
+            correct,
+            points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_question() {
+        let question = Question::from_payload("q1|Text?|A|B|C").unwrap();
+        assert_eq!(question.id, "q1");
+        assert_eq!(question.text, "Text?");
+        assert_eq!(question.options, ["A", "B", "C"]);
+        assert!(question.progress.is_none());
+    }
+
+    #[test]
+    fn parses_progress() {
+        let question = Question::from_payload("q1;3/10;Sport|Text?|A|B").unwrap();
+        let progress = question.progress.unwrap();
+        assert_eq!((progress.index, progress.total), (3, 10));
+        assert_eq!(progress.category.as_deref(), Some("Sport"));
+    }
+
+    #[test]
+    fn rejects_missing_options() {
+        assert!(Question::from_payload("q1|Text?").is_none());
+        assert!(Question::from_payload("q1").is_none());
+    }
+
+    #[test]
+    fn rejects_more_options_than_slots() {
+        assert!(Question::from_payload("q1|Text?|A|B|C|D").is_some());
+        assert!(Question::from_payload("q1|Text?|A|B|C|D|E").is_none());
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut question = Question::from_payload("q1|Text?|A|B|C").unwrap();
+        question.select_previous();
+        assert_eq!(question.selection(), 2);
+        question.select_next();
+        assert_eq!(question.selection(), 0);
+    }
+}
@@ -0,0 +1,396 @@
+use crate::diagnostics::MqttState;
+use crate::event::DeviceEvent;
+use crate::gesture::Button;
+use anyhow::{anyhow, bail};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Recorded event. Images are kept as their format and size only,
+/// as their data would make the recording, and dumps of it, too big.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Record {
+    Event(DeviceEvent),
+    Image { format: &'static str, size: usize },
+}
+
+impl From<&DeviceEvent> for Record {
+    fn from(event: &DeviceEvent) -> Self {
+        match event {
+            DeviceEvent::Image { data } => Record::Image {
+                format: image_format(data),
+                size: data.len(),
+            },
+            event => Record::Event(event.clone()),
+        }
+    }
+}
+
+/// Keeps the last `capacity` events with their timestamps,
+/// so a sequence that led to a bug can be dumped and replayed later.
+pub struct EventRecorder {
+    events: VecDeque<(Duration, Record)>,
+    capacity: usize,
+    start: Instant,
+}
+
+impl EventRecorder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, event: &DeviceEvent) {
+        // Replays would fill the whole buffer with a single, huge event
+        if matches!(event, DeviceEvent::Replay { .. }) {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events
+            .push_back((self.start.elapsed(), Record::from(event)));
+    }
+
+    /// Formats recorded events, one per line, as `<ms since boot> <event> [data]`.
+    /// The output can be read back with `parse_dump`.
+    pub fn dump(&self) -> String {
+        let mut dump = String::new();
+        for (at, record) in &self.events {
+            writeln!(dump, "{} {}", at.as_millis(), record.to_line()).unwrap();
+        }
+        dump
+    }
+}
+
+/// Parses output of `EventRecorder::dump`.
+/// Timestamps are made relative to the first event and have to be in order,
+/// dumps arrive over the network, so they aren't trusted.
+pub fn parse_dump(dump: &str) -> anyhow::Result<Vec<(Duration, Record)>> {
+    let mut events = Vec::new();
+    let mut first = None;
+    let mut previous = Duration::ZERO;
+    for line in dump.lines().filter(|line| !line.trim().is_empty()) {
+        let (at, record) = line
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Missing timestamp in `{line}`"))?;
+        let at = Duration::from_millis(at.parse()?);
+        if at < previous {
+            bail!("Timestamp of `{line}` is earlier than the previous one");
+        }
+        previous = at;
+        let first = *first.get_or_insert(at);
+        events.push((at - first, Record::from_line(record)?));
+    }
+    Ok(events)
+}
+
+/// Sends `events` to mpsc channel, keeping the original time between them.
+///
+/// Nested replays are skipped, so a replay can't trigger itself forever.
+/// `Confirm` is skipped too, as it would publish the recorded answers again,
+/// so replayed sessions only move the cursor around. Images can't be replayed,
+/// only their format and size were recorded.
+pub fn replay(events: Vec<(Duration, Record)>, sender: &mpsc::Sender<DeviceEvent>) {
+    let start = Instant::now();
+    for (at, event) in events.into_iter().filter_map(|(at, record)| match record {
+        Record::Event(DeviceEvent::Replay { .. } | DeviceEvent::Confirm) | Record::Image { .. } => {
+            None
+        }
+        Record::Event(event) => Some((at, event)),
+    }) {
+        thread::sleep(at.saturating_sub(start.elapsed()));
+        if sender.send(event).is_err() {
+            break;
+        }
+    }
+}
+
+fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::Select => "select",
+        Button::Enter => "enter",
+    }
+}
+
+fn parse_button(name: &str) -> anyhow::Result<Button> {
+    match name {
+        "select" => Ok(Button::Select),
+        "enter" => Ok(Button::Enter),
+        _ => bail!("Unknown button `{name}`"),
+    }
+}
+
+/// Keeps every record on a single line
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(c) => bail!("Unknown escape `\\{c}`"),
+            None => bail!("Unfinished escape at the end of `{text}`"),
+        }
+    }
+    Ok(out)
+}
+
+/// Recognizes images the same way `Picture::parse` does
+fn image_format(data: &[u8]) -> &'static str {
+    if data.starts_with(b"BM") {
+        "bmp"
+    } else if data.starts_with(b"qoif") {
+        "qoi"
+    } else {
+        "unknown"
+    }
+}
+
+impl Record {
+    fn to_line(&self) -> String {
+        match self {
+            Record::Event(event) => event.to_record(),
+            Record::Image { format, size } => format!("image {format} {size}"),
+        }
+    }
+
+    fn from_line(line: &str) -> anyhow::Result<Self> {
+        let (kind, data) = line.split_once(' ').unwrap_or((line, ""));
+        if kind != "image" {
+            return DeviceEvent::from_record(line).map(Record::Event);
+        }
+        let (format, size) = data
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Missing image size in `{line}`"))?;
+        Ok(Record::Image {
+            format: match format {
+                "bmp" => "bmp",
+                "qoi" => "qoi",
+                "unknown" => "unknown",
+                _ => bail!("Unknown image format `{format}`"),
+            },
+            size: size.parse()?,
+        })
+    }
+}
+
+impl DeviceEvent {
+    fn to_record(&self) -> String {
+        match self {
+            DeviceEvent::Sleep => String::from("sleep"),
+            DeviceEvent::Question { data } => format!("question {}", escape(data)),
+            DeviceEvent::Winner { data } => format!("winner {}", escape(data)),
+            DeviceEvent::Message { data } => format!("message {}", escape(data)),
+            DeviceEvent::Image { .. } => Record::from(self).to_line(),
+            DeviceEvent::Result { data } => format!("result {}", escape(data)),
+            DeviceEvent::DumpEvents => String::from("dump"),
+            DeviceEvent::Replay { data } => format!("replay {}", escape(data)),
+            DeviceEvent::MqttState { data } => format!("mqtt_state {data}"),
+            DeviceEvent::NetworkError { data } => format!("network_error {}", escape(data)),
+            DeviceEvent::Next => String::from("next"),
+            DeviceEvent::Previous => String::from("previous"),
+            DeviceEvent::Confirm => String::from("confirm"),
+            DeviceEvent::LongPress { data } => format!("long_press {}", button_name(*data)),
+            DeviceEvent::DoublePress { data } => format!("double_press {}", button_name(*data)),
+            DeviceEvent::Chord => String::from("chord"),
+            DeviceEvent::BatteryLevel { data: Some(level) } => format!("battery {level}"),
+            DeviceEvent::BatteryLevel { data: None } => String::from("battery charging"),
+        }
+    }
+
+    fn from_record(record: &str) -> anyhow::Result<Self> {
+        let (kind, data) = record.split_once(' ').unwrap_or((record, ""));
+        let text = || unescape(data).map(String::into_boxed_str);
+        Ok(match kind {
+            "sleep" => DeviceEvent::Sleep,
+            "question" => DeviceEvent::Question { data: text()? },
+            "winner" => DeviceEvent::Winner { data: text()? },
+            "message" => DeviceEvent::Message { data: text()? },
+            "result" => DeviceEvent::Result { data: text()? },
+            "dump" => DeviceEvent::DumpEvents,
+            "replay" => DeviceEvent::Replay { data: text()? },
+            "mqtt_state" => DeviceEvent::MqttState {
+                data: match data {
+                    "connecting" => MqttState::Connecting,
+                    "connected" => MqttState::Connected,
+                    "disconnected" => MqttState::Disconnected,
+                    _ => bail!("Unknown MQTT state `{data}`"),
+                },
+            },
+            "network_error" => DeviceEvent::NetworkError { data: text()? },
+            "next" => DeviceEvent::Next,
+            "previous" => DeviceEvent::Previous,
+            "confirm" => DeviceEvent::Confirm,
+            "long_press" => DeviceEvent::LongPress {
+                data: parse_button(data)?,
+            },
+            "double_press" => DeviceEvent::DoublePress {
+                data: parse_button(data)?,
+            },
+            "chord" => DeviceEvent::Chord,
+            "battery" => DeviceEvent::BatteryLevel {
+                data: match data {
+                    "charging" => None,
+                    level => Some(level.parse()?),
+                },
+            },
+            _ => bail!("Unknown event `{kind}`"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<DeviceEvent> {
+        vec![
+            DeviceEvent::Question {
+                data: "q1|Line\\none\nline two|A|B".into(),
+            },
+            DeviceEvent::MqttState {
+                data: MqttState::Connected,
+            },
+            DeviceEvent::Next,
+            DeviceEvent::LongPress {
+                data: Button::Enter,
+            },
+            DeviceEvent::DoublePress {
+                data: Button::Select,
+            },
+            DeviceEvent::BatteryLevel { data: Some(42) },
+            DeviceEvent::BatteryLevel { data: None },
+            DeviceEvent::NetworkError {
+                data: "Wi-Fi: disconnected".into(),
+            },
+            DeviceEvent::Confirm,
+        ]
+    }
+
+    fn records(dump: &str) -> Vec<Record> {
+        parse_dump(dump)
+            .unwrap()
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut recorder = EventRecorder::new(16);
+        for event in events() {
+            recorder.record(&event);
+        }
+        let expected: Vec<_> = events().into_iter().map(Record::Event).collect();
+        assert_eq!(records(&recorder.dump()), expected);
+    }
+
+    #[test]
+    fn keeps_last_events() {
+        let mut recorder = EventRecorder::new(2);
+        for event in events() {
+            recorder.record(&event);
+        }
+        let expected: Vec<_> = events()[7..].iter().cloned().map(Record::Event).collect();
+        assert_eq!(records(&recorder.dump()), expected);
+    }
+
+    #[test]
+    fn images_are_recorded_as_format_and_size() {
+        let mut recorder = EventRecorder::new(4);
+        recorder.record(&DeviceEvent::Image {
+            data: include_bytes!("logo.bmp").as_slice().into(),
+        });
+        recorder.record(&DeviceEvent::Image {
+            data: b"qoif\0\0".as_slice().into(),
+        });
+        recorder.record(&DeviceEvent::Replay {
+            data: "0 next".into(),
+        });
+        let dump = recorder.dump();
+        assert!(!dump.contains("replay"));
+        assert_eq!(
+            records(&dump),
+            [
+                Record::Image {
+                    format: "bmp",
+                    size: include_bytes!("logo.bmp").len(),
+                },
+                Record::Image {
+                    format: "qoi",
+                    size: 6
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn timestamps_are_relative_to_first() {
+        let events = parse_dump("1000 next\n\n1250 previous\n1250 chord\n").unwrap();
+        let at: Vec<_> = events.iter().map(|(at, _)| at.as_millis()).collect();
+        assert_eq!(at, [0, 250, 250]);
+    }
+
+    #[test]
+    fn rejects_timestamps_out_of_order() {
+        assert!(parse_dump("1000 next\n900 previous").is_err());
+        assert!(parse_dump("next").is_err());
+        assert!(parse_dump("soon next").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_tags() {
+        assert!(parse_dump("0 jump").is_err());
+        assert!(parse_dump("0 mqtt_state sleeping").is_err());
+        assert!(parse_dump("0 long_press both").is_err());
+        assert!(parse_dump("0 image png 100").is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_records() {
+        assert!(parse_dump("0 image bmp").is_err());
+        assert!(parse_dump("0 image").is_err());
+        assert!(parse_dump("0 image bmp 12ab").is_err());
+        assert!(parse_dump("0 battery").is_err());
+        assert!(parse_dump("0 long_press").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_escapes() {
+        assert!(parse_dump("0 message tab\\there").is_err());
+        assert!(parse_dump("0 message trailing\\").is_err());
+        assert_eq!(
+            records("0 message a\\\\n\\nb"),
+            [Record::Event(DeviceEvent::Message {
+                data: "a\\n\nb".into()
+            })]
+        );
+    }
+
+    #[test]
+    fn replay_skips_confirm_images_and_replays() {
+        let (sender, receiver) = mpsc::channel();
+        let events =
+            parse_dump("0 next\n0 confirm\n0 image bmp 10\n0 replay 0 next\n0 previous").unwrap();
+        replay(events, &sender);
+        drop(sender);
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            [DeviceEvent::Next, DeviceEvent::Previous]
+        );
+    }
+}
@@ -0,0 +1,439 @@
+use crate::animation::Animation;
+use crate::diagnostics::NetworkDiagnostics;
+use crate::layout::ScreenLayout;
+use crate::markup::{Markup, MarkupLayout, MarkupView};
+use crate::picture::Picture;
+use crate::qr::QrCodeView;
+use crate::quiz::Progress;
+use crate::status_bar::{StatusBar, StatusBarState};
+use crate::text_layout::{Font, TextLayout};
+use crate::theme::{Theme, DARK};
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, Polyline, PrimitiveStyle, Rectangle, StyledDrawable};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
+use embedded_text::alignment::{HorizontalAlignment, VerticalAlignment};
+use embedded_text::style::TextBoxStyleBuilder;
+use embedded_text::TextBox;
+use qrcodegen::QrCode;
+
+/// Event logo shown on the idle screen
+const LOGO: &[u8] = include_bytes!("logo.bmp");
+
+/// Text, that doesn't fit on the screen, is split into pages.
+/// `page` selects which one to draw and wraps around, so it can be a simple counter.
+pub trait QuizRenderer {
+    fn draw_question(&mut self, question: &str, progress: Option<&Progress>, page: usize);
+    fn draw_options(&mut self, options: &[String], selected: usize, page: usize);
+    /// Marks the correct option and the player's choice, with points earned in place of the question.
+    fn draw_result(
+        &mut self,
+        options: &[String],
+        chosen: usize,
+        correct: usize,
+        points: i32,
+        page: usize,
+    );
+    fn draw_text(&mut self, text: &str, page: usize);
+    fn draw_markup(&mut self, markup: &Markup, page: usize);
+    fn draw_qr_code(&mut self, code: &QrCode);
+    /// Draws a BMP or QOI file, invalid ones are skipped.
+    fn draw_image(&mut self, data: &[u8]);
+    /// Shown between questions: the event logo, `name` of the device and the time, when known.
+    fn draw_idle(&mut self, name: &str, time: Option<(u8, u8)>);
+    /// Draws the current frame of `animation` over whatever is on the screen.
+    fn draw_animation(&mut self, animation: &dyn Animation);
+    /// Area below the status bar, where animations take place
+    fn content_area(&self) -> Rectangle;
+    fn draw_status_bar(&mut self, status: &StatusBarState);
+    fn draw_diagnostics(&mut self, diagnostics: &NetworkDiagnostics);
+    /// Takes effect on the next redraw.
+    fn set_theme(&mut self, theme: &'static Theme);
+    /// Most pages any text drawn since the last clear was split into,
+    /// so pages are only turned when there's more than one.
+    fn page_count(&self) -> usize;
+}
+
+/// Draws quiz screens into any `DrawTarget`, e.g. the display's framebuffer,
+/// or an in-memory one, when checking what screens look like.
+pub struct Renderer<D> {
+    target: D,
+    layout: ScreenLayout,
+    status_bar: StatusBar,
+    theme: &'static Theme,
+    /// See `QuizRenderer::page_count`
+    pages: usize,
+}
+
+impl<D> Renderer<D>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    pub fn new(target: D) -> Self {
+        let layout = ScreenLayout::new(target.bounding_box().size);
+        Self {
+            target,
+            status_bar: StatusBar::new(layout.status_bar),
+            layout,
+            theme: &DARK,
+            pages: 1,
+        }
+    }
+
+    pub fn target_mut(&mut self) -> &mut D {
+        &mut self.target
+    }
+
+    /// Fills everything except the status bar with the background color.
+    pub fn clear(&mut self) {
+        self.pages = 1;
+        self.target
+            .fill_solid(&self.layout.content, self.theme.background)
+            .ok();
+    }
+}
+
+/// Symbol in front of an option
+#[derive(Clone, Copy)]
+enum Mark {
+    Blank,
+    Arrow,
+    Check,
+    Cross,
+}
+
+/// Draws `mark` in front of an option, optionally framing the whole option,
+/// so marked options don't depend on color alone.
+fn draw_mark<D>(
+    display: &mut D,
+    cell: Rectangle,
+    mark: Mark,
+    framed: bool,
+    color: Rgb565,
+    theme: &Theme,
+) where
+    D: DrawTarget<Color = Rgb565>,
+{
+    // Unmarked options are drawn over with the background color,
+    // so we don't have to re-render the whole screen.
+    let middle = cell.top_left + Point::new(0, cell.size.height as i32 / 2);
+    Rectangle::new(middle - Point::new(0, 10), Size::new(10, 20))
+        .draw_styled(&PrimitiveStyle::with_fill(theme.background), display)
+        .ok();
+    let stroke = PrimitiveStyle::with_stroke(color, 2);
+    match mark {
+        Mark::Blank => {}
+        Mark::Arrow => {
+            Text::with_baseline(">", middle, Font::Large.style(color), Baseline::Middle)
+                .draw(display)
+                .ok();
+        }
+        Mark::Check => {
+            Polyline::new(&[
+                middle + Point::new(1, 0),
+                middle + Point::new(4, 4),
+                middle + Point::new(9, -5),
+            ])
+            .draw_styled(&stroke, display)
+            .ok();
+        }
+        Mark::Cross => {
+            Line::new(middle + Point::new(1, -4), middle + Point::new(9, 4))
+                .draw_styled(&stroke, display)
+                .ok();
+            Line::new(middle + Point::new(1, 4), middle + Point::new(9, -4))
+                .draw_styled(&stroke, display)
+                .ok();
+        }
+    }
+    Rectangle::new(
+        cell.top_left + Point::new(0, 2),
+        cell.size.saturating_sub(Size::new(0, 3)),
+    )
+    .draw_styled(
+        &PrimitiveStyle::with_stroke(if framed { color } else { theme.background }, 1),
+        display,
+    )
+    .ok();
+}
+
+fn draw_option<D>(
+    display: &mut D,
+    cell: Rectangle,
+    option: &str,
+    color: Rgb565,
+    page: usize,
+    theme: &Theme,
+) -> usize
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    Line::new(
+        cell.top_left,
+        cell.top_left + Point::new(cell.size.width as i32, 0),
+    )
+    .draw_styled(&PrimitiveStyle::with_stroke(theme.accent, 1), display)
+    .ok();
+    draw_fitted_text(
+        display,
+        option,
+        Rectangle::new(
+            cell.top_left + Point::new(16, 0),
+            Size::new(cell.size.width.saturating_sub(18), cell.size.height),
+        ),
+        color,
+        page,
+    )
+}
+
+/// Draws "3/10 Category" with a thin progress bar under it.
+fn draw_progress<D>(display: &mut D, progress: &Progress, area: Rectangle, theme: &Theme)
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let label = match &progress.category {
+        Some(category) => format!("{}/{} {}", progress.index, progress.total, category),
+        None => format!("{}/{}", progress.index, progress.total),
+    };
+    Text::with_baseline(
+        &label,
+        area.top_left + Point::new(2, 1),
+        Font::Small.style(theme.text),
+        Baseline::Top,
+    )
+    .draw(&mut display.clipped(&area))
+    .ok();
+    let bar = Rectangle::new(
+        area.top_left + Point::new(0, area.size.height as i32 - 3),
+        Size::new(area.size.width, 2),
+    );
+    bar.draw_styled(&PrimitiveStyle::with_fill(theme.accent), display)
+        .ok();
+    Rectangle::new(
+        bar.top_left,
+        Size::new(
+            bar.size.width * progress.index / progress.total,
+            bar.size.height,
+        ),
+    )
+    .draw_styled(&PrimitiveStyle::with_fill(theme.selection), display)
+    .ok();
+}
+
+/// Draws a page of `text` fitted into `area` with the largest font possible,
+/// centered vertically. Returns the number of pages.
+fn draw_fitted_text<D>(
+    display: &mut D,
+    text: &str,
+    area: Rectangle,
+    color: Rgb565,
+    page: usize,
+) -> usize
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let layout = TextLayout::fit(text, area.size);
+    let line_height = layout.font.char_size().height;
+    let lines = layout.page(page);
+    let y_offset = area
+        .size
+        .height
+        .saturating_sub(lines.len() as u32 * line_height)
+        / 2;
+    for (idx, line) in lines.iter().enumerate() {
+        let y = (y_offset + idx as u32 * line_height) as i32;
+        Text::with_baseline(
+            line,
+            area.top_left + Point::new(0, y),
+            layout.font.style(color),
+            Baseline::Top,
+        )
+        .draw(display)
+        .ok();
+    }
+    layout.page_count()
+}
+
+/// Splits `height` pixels off the bottom of `area`.
+fn split_bottom(area: Rectangle, height: u32) -> (Rectangle, Rectangle) {
+    let height = height.min(area.size.height);
+    let top = Rectangle::new(
+        area.top_left,
+        Size::new(area.size.width, area.size.height - height),
+    );
+    let bottom = Rectangle::new(
+        area.top_left + Point::new(0, top.size.height as i32),
+        Size::new(area.size.width, height),
+    );
+    (top, bottom)
+}
+
+impl<D> QuizRenderer for Renderer<D>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    fn draw_question(&mut self, question: &str, progress: Option<&Progress>, page: usize) {
+        let area = match progress {
+            Some(progress) => {
+                let (header, area) = self.layout.split_progress();
+                draw_progress(&mut self.target, progress, header, self.theme);
+                area
+            }
+            None => self.layout.question,
+        };
+        let pages = draw_fitted_text(&mut self.target, question, area, self.theme.text, page);
+        self.pages = self.pages.max(pages);
+    }
+
+    fn draw_options(&mut self, options: &[String], selection: usize, page: usize) {
+        for (idx, option) in options.iter().enumerate() {
+            let selected = idx == selection;
+            let cell = self.layout.option(idx);
+            let (mark, color) = match selected {
+                true => (Mark::Arrow, self.theme.selection),
+                false => (Mark::Blank, self.theme.text),
+            };
+            draw_mark(&mut self.target, cell, mark, selected, color, self.theme);
+            let pages = draw_option(&mut self.target, cell, option, color, page, self.theme);
+            self.pages = self.pages.max(pages);
+        }
+    }
+
+    fn draw_result(
+        &mut self,
+        options: &[String],
+        chosen: usize,
+        correct: usize,
+        points: i32,
+        page: usize,
+    ) {
+        let summary = match chosen == correct {
+            true => format!("Correct!\n{points:+} points"),
+            false => format!("Wrong!\n{points:+} points"),
+        };
+        let pages = draw_fitted_text(
+            &mut self.target,
+            &summary,
+            self.layout.question,
+            self.theme.text,
+            page,
+        );
+        self.pages = self.pages.max(pages);
+        for (idx, option) in options.iter().enumerate() {
+            let cell = self.layout.option(idx);
+            let (mark, color) = if idx == correct {
+                (Mark::Check, self.theme.selection)
+            } else if idx == chosen {
+                (Mark::Cross, self.theme.warning)
+            } else {
+                (Mark::Blank, self.theme.text)
+            };
+            draw_mark(
+                &mut self.target,
+                cell,
+                mark,
+                idx == chosen,
+                color,
+                self.theme,
+            );
+            let pages = draw_option(&mut self.target, cell, option, color, page, self.theme);
+            self.pages = self.pages.max(pages);
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, page: usize) {
+        let pages = draw_fitted_text(
+            &mut self.target,
+            text,
+            self.layout.content,
+            self.theme.text,
+            page,
+        );
+        self.pages = self.pages.max(pages);
+    }
+
+    fn draw_markup(&mut self, markup: &Markup, page: usize) {
+        let layout = MarkupLayout::fit(markup, self.layout.content.size);
+        self.pages = self.pages.max(layout.page_count());
+        MarkupView::new(&layout, self.layout.content, page, self.theme)
+            .draw(&mut self.target)
+            .ok();
+    }
+
+    fn draw_qr_code(&mut self, code: &QrCode) {
+        QrCodeView::new(code, self.layout.content)
+            .draw(&mut self.target)
+            .ok();
+    }
+
+    fn draw_image(&mut self, data: &[u8]) {
+        if let Ok(picture) = Picture::parse(data) {
+            picture
+                .draw_centered(&mut self.target, self.layout.content)
+                .ok();
+        }
+    }
+
+    fn draw_idle(&mut self, name: &str, time: Option<(u8, u8)>) {
+        let Ok(logo) = Picture::parse(LOGO) else {
+            return;
+        };
+        let (logo_area, text_area) = self.layout.split_idle(logo.size());
+        logo.draw_fitted(&mut self.target, logo_area).ok();
+        let name_area = match time {
+            Some((hour, minute)) => {
+                let line_height = Font::Large.char_size().height;
+                let (name_area, clock_area) = split_bottom(text_area, line_height);
+                Text::with_text_style(
+                    &format!("{hour:02}:{minute:02}"),
+                    clock_area.center(),
+                    Font::Large.style(self.theme.accent),
+                    TextStyleBuilder::new()
+                        .alignment(Alignment::Center)
+                        .baseline(Baseline::Middle)
+                        .build(),
+                )
+                .draw(&mut self.target)
+                .ok();
+                name_area
+            }
+            None => text_area,
+        };
+        draw_fitted_text(&mut self.target, name, name_area, self.theme.text, 0);
+    }
+
+    fn draw_animation(&mut self, animation: &dyn Animation) {
+        animation.draw(&mut self.target.clipped(&self.layout.content), self.theme);
+    }
+
+    fn content_area(&self) -> Rectangle {
+        self.layout.content
+    }
+
+    fn draw_status_bar(&mut self, status: &StatusBarState) {
+        self.status_bar.draw(&mut self.target, status, self.theme);
+    }
+
+    fn draw_diagnostics(&mut self, diagnostics: &NetworkDiagnostics) {
+        TextBox::with_textbox_style(
+            &diagnostics.to_string(),
+            self.layout.content,
+            Font::Small.style(self.theme.text),
+            TextBoxStyleBuilder::new()
+                .alignment(HorizontalAlignment::Left)
+                .vertical_alignment(VerticalAlignment::Top)
+                .build(),
+        )
+        .draw(&mut self.target)
+        .ok();
+    }
+
+    fn set_theme(&mut self, theme: &'static Theme) {
+        self.theme = theme;
+        self.status_bar.invalidate();
+    }
+
+    fn page_count(&self) -> usize {
+        self.pages
+    }
+}
@@ -0,0 +1,205 @@
+use crate::config::LOW_BATTERY_LEVEL;
+use crate::diagnostics::MqttState;
+use crate::text_layout::Font;
+use crate::theme::Theme;
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{
+    Circle, Line, PrimitiveStyle, Rectangle, StyledDrawable, Triangle,
+};
+use embedded_graphics::text::{Baseline, Text};
+
+/// Everything shown in the status bar.
+#[derive(Clone, Copy, PartialEq)]
+pub struct StatusBarState {
+    /// `None` while charging
+    pub battery_level: Option<u8>,
+    /// `None` while disconnected from the access point
+    pub rssi: Option<i8>,
+    pub mqtt_state: MqttState,
+    /// Local hours and minutes, `None` hides the clock
+    pub time: Option<(u8, u8)>,
+}
+
+/// Status bar with (from the right) battery gauge, Wi-Fi signal bars,
+/// broker connection indicator and a clock.
+///
+/// Remembers what it drew last time, and redraws only icons, whose state changed.
+pub struct StatusBar {
+    area: Rectangle,
+    drawn: Option<StatusBarState>,
+}
+
+impl StatusBar {
+    pub fn new(area: Rectangle) -> Self {
+        Self { area, drawn: None }
+    }
+
+    /// Makes the next `draw` redraw everything, e.g. after the theme changed.
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+
+    pub fn draw<D>(&mut self, display: &mut D, state: &StatusBarState, theme: &Theme)
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let drawn = self.drawn.replace(*state);
+        if drawn.is_none() {
+            // Gaps between icons may still have the previous theme's background
+            display.fill_solid(&self.area, theme.background).ok();
+        }
+        if drawn.map_or(true, |drawn| drawn.battery_level != state.battery_level) {
+            self.draw_battery(display, state.battery_level, theme);
+        }
+        if drawn.map_or(true, |drawn| drawn.rssi != state.rssi) {
+            self.draw_wifi(display, state.rssi, theme);
+        }
+        if drawn.map_or(true, |drawn| drawn.mqtt_state != state.mqtt_state) {
+            self.draw_mqtt(display, state.mqtt_state, theme);
+        }
+        if drawn.map_or(true, |drawn| drawn.time != state.time) {
+            self.draw_clock(display, state.time, theme);
+        }
+    }
+
+    /// Area of an icon `width` pixels wide, `right` pixels from the right edge.
+    fn icon_area(&self, right: u32, width: u32) -> Rectangle {
+        Rectangle::new(
+            self.area.top_left
+                + Point::new(self.area.size.width.saturating_sub(right + width) as i32, 0),
+            Size::new(width, self.area.size.height),
+        )
+    }
+
+    fn draw_battery<D>(&self, display: &mut D, battery_level: Option<u8>, theme: &Theme)
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let area = self.icon_area(2, 26);
+        clear(display, area, theme);
+        let origin = area.top_left + Point::new(0, 4);
+        Rectangle::new(origin, Size::new(23, 12))
+            .draw_styled(&PrimitiveStyle::with_stroke(theme.text, 1), display)
+            .ok();
+        // Positive terminal
+        Rectangle::new(origin + Point::new(23, 3), Size::new(2, 6))
+            .draw_styled(&PrimitiveStyle::with_fill(theme.text), display)
+            .ok();
+        match battery_level {
+            Some(level) => {
+                let color = if level <= LOW_BATTERY_LEVEL {
+                    theme.warning
+                } else {
+                    theme.text
+                };
+                let width = 19 * u32::from(level.min(100)) / 100;
+                Rectangle::new(origin + Point::new(2, 2), Size::new(width, 8))
+                    .draw_styled(&PrimitiveStyle::with_fill(color), display)
+                    .ok();
+            }
+            None => {
+                // Charging bolt
+                let style = PrimitiveStyle::with_fill(theme.selection);
+                Triangle::new(
+                    origin + Point::new(13, 1),
+                    origin + Point::new(7, 7),
+                    origin + Point::new(12, 7),
+                )
+                .draw_styled(&style, display)
+                .ok();
+                Triangle::new(
+                    origin + Point::new(11, 5),
+                    origin + Point::new(16, 5),
+                    origin + Point::new(10, 11),
+                )
+                .draw_styled(&style, display)
+                .ok();
+            }
+        }
+    }
+
+    fn draw_wifi<D>(&self, display: &mut D, rssi: Option<i8>, theme: &Theme)
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let area = self.icon_area(32, 16);
+        clear(display, area, theme);
+        let bars = match rssi {
+            None => 0,
+            Some(rssi) if rssi >= -55 => 4,
+            Some(rssi) if rssi >= -65 => 3,
+            Some(rssi) if rssi >= -75 => 2,
+            Some(_) => 1,
+        };
+        for bar in 0..4 {
+            let height = 3 * (bar + 1);
+            let color = if bar < bars { theme.text } else { theme.accent };
+            Rectangle::new(
+                area.top_left + Point::new(bar as i32 * 4, 16 - height as i32),
+                Size::new(3, height),
+            )
+            .draw_styled(&PrimitiveStyle::with_fill(color), display)
+            .ok();
+        }
+        if rssi.is_none() {
+            Line::new(
+                area.top_left + Point::new(0, 4),
+                area.top_left + Point::new(14, 16),
+            )
+            .draw_styled(&PrimitiveStyle::with_stroke(theme.warning, 1), display)
+            .ok();
+        }
+    }
+
+    fn draw_mqtt<D>(&self, display: &mut D, mqtt_state: MqttState, theme: &Theme)
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let area = self.icon_area(52, 12);
+        clear(display, area, theme);
+        // Filled when connected, hollow while connecting and crossed out when disconnected
+        let style = match mqtt_state {
+            MqttState::Connected => PrimitiveStyle::with_fill(theme.text),
+            MqttState::Connecting => PrimitiveStyle::with_stroke(theme.text, 1),
+            MqttState::Disconnected => PrimitiveStyle::with_stroke(theme.warning, 1),
+        };
+        Circle::new(area.top_left + Point::new(0, 5), 11)
+            .draw_styled(&style, display)
+            .ok();
+        if mqtt_state == MqttState::Disconnected {
+            Line::new(
+                area.top_left + Point::new(1, 15),
+                area.top_left + Point::new(10, 6),
+            )
+            .draw_styled(&style, display)
+            .ok();
+        }
+    }
+
+    fn draw_clock<D>(&self, display: &mut D, time: Option<(u8, u8)>, theme: &Theme)
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let area = Rectangle::new(self.area.top_left, Size::new(48, self.area.size.height));
+        clear(display, area, theme);
+        if let Some((hours, minutes)) = time {
+            Text::with_baseline(
+                &format!("{hours:02}:{minutes:02}"),
+                area.top_left + Point::new(2, 3),
+                Font::Medium.style(theme.text),
+                Baseline::Top,
+            )
+            .draw(display)
+            .ok();
+        }
+    }
+}
+
+fn clear<D>(display: &mut D, area: Rectangle, theme: &Theme)
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    area.draw_styled(&PrimitiveStyle::with_fill(theme.background), display)
+        .ok();
+}
@@ -0,0 +1,176 @@
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use u8g2_fonts::fonts;
+use u8g2_fonts::U8g2TextStyle;
+
+/// Fonts, the layout can choose from.
+/// All of them are monospaced X11 fonts in their `_te` variant,
+/// which includes Latin Extended-A, so Polish diacritics render properly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Font {
+    Large,
+    Medium,
+    Small,
+}
+
+impl Font {
+    /// From the largest one
+    pub const ALL: [Font; 3] = [Font::Large, Font::Medium, Font::Small];
+
+    /// Size of a single character (all fonts are monospaced).
+    pub fn char_size(self) -> Size {
+        match self {
+            Font::Large => Size::new(10, 20),
+            Font::Medium => Size::new(8, 13),
+            Font::Small => Size::new(6, 12),
+        }
+    }
+
+    pub fn style(self, color: Rgb565) -> U8g2TextStyle<Rgb565> {
+        match self {
+            Font::Large => U8g2TextStyle::new(fonts::u8g2_font_10x20_te, color),
+            Font::Medium => U8g2TextStyle::new(fonts::u8g2_font_8x13_te, color),
+            Font::Small => U8g2TextStyle::new(fonts::u8g2_font_6x12_te, color),
+        }
+    }
+}
+
+/// Text broken into lines, that fit into a given area.
+/// When the text doesn't fit even with the smallest font, it's split into pages,
+/// which are meant to be shown one after another.
+pub struct TextLayout {
+    pub font: Font,
+    pages: Vec<Vec<String>>,
+}
+
+impl TextLayout {
+    /// Picks the largest font, with which `text` fits into `size`,
+    /// falling back to paging with the smallest font.
+    pub fn fit(text: &str, size: Size) -> Self {
+        for font in Font::ALL {
+            let (columns, rows) = Self::capacity(font, size);
+            let lines = wrap(text, columns);
+            if lines.len() <= rows {
+                return Self {
+                    font,
+                    pages: vec![lines],
+                };
+            }
+        }
+        let font = Font::Small;
+        let (columns, rows) = Self::capacity(font, size);
+        let pages = wrap(text, columns)
+            .chunks(rows.max(1))
+            .map(<[String]>::to_vec)
+            .collect();
+        Self { font, pages }
+    }
+
+    /// Returns how many columns and rows of `font` characters fit into `size`.
+    pub fn capacity(font: Font, size: Size) -> (usize, usize) {
+        let char_size = font.char_size();
+        (
+            (size.width / char_size.width) as usize,
+            (size.height / char_size.height) as usize,
+        )
+    }
+
+    /// Returns lines of the given page, wrapping around after the last one,
+    /// so a page counter can simply keep increasing.
+    pub fn page(&self, page: usize) -> &[String] {
+        &self.pages[page % self.pages.len()]
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+/// Breaks `text` into lines of at most `columns` characters.
+/// Lines are broken on spaces and explicit new lines,
+/// words longer than a line are broken wherever they have to.
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_length = 0;
+        for word in paragraph.split(' ').filter(|word| !word.is_empty()) {
+            let mut word_length = word.chars().count();
+            if line_length > 0 && line_length + 1 + word_length > columns {
+                lines.push(std::mem::take(&mut line));
+                line_length = 0;
+            }
+            if line_length > 0 {
+                line.push(' ');
+                line_length += 1;
+            }
+            let mut chars = word.chars();
+            while line_length + word_length > columns {
+                let fits = columns - line_length;
+                line.extend(chars.by_ref().take(fits));
+                lines.push(std::mem::take(&mut line));
+                line_length = 0;
+                word_length -= fits;
+            }
+            line.extend(chars);
+            line_length += word_length;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_on_spaces() {
+        assert_eq!(wrap("one two  three", 8), ["one two", "three"]);
+        assert_eq!(wrap("Zażółć gęślą jaźń", 12), ["Zażółć gęślą", "jaźń"]);
+    }
+
+    #[test]
+    fn splits_long_words() {
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("ab abcdefgh", 4), ["ab", "abcd", "efgh"]);
+        assert_eq!(wrap("abc defghi", 5), ["abc", "defgh", "i"]);
+    }
+
+    #[test]
+    fn keeps_explicit_new_lines() {
+        assert_eq!(wrap("a\nb c", 10), ["a", "b c"]);
+        assert_eq!(wrap("a\n\nb", 10), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn picks_largest_font_that_fits() {
+        let size = Size::new(100, 40);
+        let layout = TextLayout::fit("aaaa bbbb", size);
+        assert_eq!(layout.font, Font::Large);
+        assert_eq!(layout.page_count(), 1);
+
+        let layout = TextLayout::fit("aaaa bbbb cccc dddd eeee", size);
+        assert_eq!(layout.font, Font::Medium);
+        assert_eq!(layout.page(0), ["aaaa bbbb", "cccc dddd", "eeee"]);
+
+        let layout = TextLayout::fit("aaaa bbbb cccc dddd eeee ffff gggg", size);
+        assert_eq!(layout.font, Font::Small);
+        assert_eq!(layout.page(0), ["aaaa bbbb cccc", "dddd eeee ffff", "gggg"]);
+        assert_eq!(layout.page_count(), 1);
+    }
+
+    #[test]
+    fn splits_into_pages_with_smallest_font() {
+        // 10 columns and 2 rows of the small font
+        let layout = TextLayout::fit("one two three four five six seven eight", Size::new(60, 24));
+        assert_eq!(layout.font, Font::Small);
+        assert_eq!(layout.page_count(), 3);
+        assert_eq!(layout.page(0), ["one two", "three four"]);
+        assert_eq!(layout.page(1), ["five six", "seven"]);
+        assert_eq!(layout.page(2), ["eight"]);
+        // Wraps around after the last page
+        assert_eq!(layout.page(3), layout.page(0));
+    }
+}
@@ -0,0 +1,55 @@
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+
+/// Colors used by `QuizRenderer`.
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Rgb565,
+    pub text: Rgb565,
+    /// Separators and other decorations
+    pub accent: Rgb565,
+    /// Selected option, which is also marked with an arrow and a frame,
+    /// so it can be told apart without relying on color
+    pub selection: Rgb565,
+    /// Things that need attention, e.g. low battery
+    pub warning: Rgb565,
+}
+
+pub const DARK: Theme = Theme {
+    name: "dark",
+    background: Rgb565::BLACK,
+    text: Rgb565::WHITE,
+    accent: Rgb565::new(16, 32, 16),
+    selection: Rgb565::GREEN,
+    warning: Rgb565::RED,
+};
+
+pub const LIGHT: Theme = Theme {
+    name: "light",
+    background: Rgb565::WHITE,
+    text: Rgb565::BLACK,
+    accent: Rgb565::new(16, 32, 16),
+    selection: Rgb565::BLUE,
+    warning: Rgb565::RED,
+};
+
+/// Only fully saturated colors, that stay distinguishable for most kinds of color blindness.
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    background: Rgb565::BLACK,
+    text: Rgb565::WHITE,
+    accent: Rgb565::WHITE,
+    selection: Rgb565::YELLOW,
+    warning: Rgb565::MAGENTA,
+};
+
+/// Built-in themes, in the order they are cycled through.
+pub const THEMES: [&Theme; 3] = [&DARK, &LIGHT, &HIGH_CONTRAST];
+
+/// Returns index of the theme called `name` in `THEMES`, defaulting to the first one.
+pub fn find(name: Option<&str>) -> usize {
+    THEMES
+        .iter()
+        .position(|theme| Some(theme.name) == name)
+        .unwrap_or(0)
+}
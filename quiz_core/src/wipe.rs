@@ -0,0 +1,87 @@
+use crate::animation::{Animation, Canvas};
+use crate::theme::Theme;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use std::time::Duration;
+
+/// Width of the line leading the wipe
+const EDGE_WIDTH: u32 = 3;
+
+/// Transition revealing the incoming screen from the left, behind a moving line.
+pub struct Wipe {
+    area: Rectangle,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl Wipe {
+    pub fn new(area: Rectangle, duration: Duration) -> Self {
+        Self {
+            area,
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Width of the part already showing the incoming screen
+    fn revealed(&self) -> u32 {
+        if self.elapsed >= self.duration {
+            return self.area.size.width;
+        }
+        (self.area.size.width as f32 * self.elapsed.as_secs_f32() / self.duration.as_secs_f32())
+            as u32
+    }
+}
+
+impl Animation for Wipe {
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        self.elapsed += elapsed;
+        self.elapsed < self.duration
+    }
+
+    /// Everything revealed so far, so the incoming screen gets redrawn there, and the edge
+    fn bounds(&self) -> Rectangle {
+        let width = (self.revealed() + EDGE_WIDTH).min(self.area.size.width);
+        Rectangle::new(self.area.top_left, Size::new(width, self.area.size.height))
+    }
+
+    fn draw(&self, canvas: &mut dyn Canvas, theme: &Theme) {
+        let edge = Rectangle::new(
+            self.area.top_left + Point::new(self.revealed() as i32, 0),
+            Size::new(EDGE_WIDTH, self.area.size.height),
+        );
+        canvas
+            .fill_solid(&edge.intersection(&self.area), theme.accent)
+            .ok();
+    }
+
+    fn is_transition(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rectangle = Rectangle::new(Point::new(0, 20), Size::new(100, 50));
+
+    #[test]
+    fn reveals_from_the_left() {
+        let mut wipe = Wipe::new(AREA, Duration::from_millis(400));
+        assert_eq!(wipe.bounds().size, Size::new(EDGE_WIDTH, 50));
+        assert!(wipe.advance(Duration::from_millis(100)));
+        assert_eq!(
+            wipe.bounds(),
+            Rectangle::new(AREA.top_left, Size::new(28, 50))
+        );
+    }
+
+    #[test]
+    fn covers_whole_area_when_finished() {
+        let mut wipe = Wipe::new(AREA, Duration::from_millis(400));
+        assert!(wipe.advance(Duration::from_millis(300)));
+        assert!(!wipe.advance(Duration::from_millis(300)));
+        assert_eq!(wipe.bounds(), AREA);
+    }
+}
@@ -0,0 +1,353 @@
+//! Renders screens into memory and compares them with approved images in `tests/snapshots`.
+//!
+//! When a screen differs, the rendered image and a diff (changed pixels in red) are written
+//! next to the test binaries and the test fails. Run with `UPDATE_SNAPSHOTS=1` to approve
+//! the current output instead.
+
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888};
+use embedded_graphics::prelude::*;
+use quiz_core::diagnostics::{MqttState, NetworkDiagnostics};
+use quiz_core::markup::Markup;
+use quiz_core::qr;
+use quiz_core::quiz::{AnswerResult, Question};
+use quiz_core::renderer::{QuizRenderer, Renderer};
+use quiz_core::status_bar::StatusBarState;
+use quiz_core::theme::{Theme, DARK, HIGH_CONTRAST, LIGHT};
+use std::convert::Infallible;
+use std::fs::File;
+use std::io::BufWriter;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const PORTRAIT: Size = Size::new(135, 240);
+const LANDSCAPE: Size = Size::new(240, 135);
+
+const STATUS: StatusBarState = StatusBarState {
+    battery_level: Some(80),
+    rssi: Some(-60),
+    mqtt_state: MqttState::Connected,
+    time: Some((12, 30)),
+};
+
+/// In-memory `DrawTarget`, standing in for the display's framebuffer.
+struct Canvas {
+    size: Size,
+    pixels: Vec<Rgb565>,
+}
+
+impl Canvas {
+    fn new(size: Size) -> Self {
+        Self {
+            size,
+            pixels: vec![Rgb565::BLACK; (size.width * size.height) as usize],
+        }
+    }
+
+    fn to_rgb(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&pixel| {
+                let pixel = Rgb888::from(pixel);
+                [pixel.r(), pixel.g(), pixel.b()]
+            })
+            .collect()
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if self.bounding_box().contains(point) {
+                let idx = point.y as u32 * self.size.width + point.x as u32;
+                self.pixels[idx as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws the status bar and whatever `content` draws, the same way the device does.
+fn render(
+    size: Size,
+    theme: &'static Theme,
+    content: impl FnOnce(&mut Renderer<Canvas>),
+) -> Canvas {
+    let mut renderer = Renderer::new(Canvas::new(size));
+    renderer.set_theme(theme);
+    renderer.clear();
+    renderer.draw_status_bar(&STATUS);
+    content(&mut renderer);
+    std::mem::replace(renderer.target_mut(), Canvas::new(Size::zero()))
+}
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+fn write_png(path: &Path, size: Size, rgb: &[u8]) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, size.width, size.height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(rgb)
+        .unwrap();
+}
+
+fn read_png(path: &Path) -> Option<(Size, Vec<u8>)> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let mut reader = decoder.read_info().unwrap();
+    let mut rgb = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgb).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgb, "{}", path.display());
+    rgb.truncate(info.buffer_size());
+    Some((Size::new(info.width, info.height), rgb))
+}
+
+/// Changed pixels in red, unchanged ones dimmed, so the changes stand out.
+fn diff(expected: &[u8], actual: &[u8]) -> Vec<u8> {
+    expected
+        .chunks(3)
+        .zip(actual.chunks(3))
+        .flat_map(|(expected, actual)| match expected == actual {
+            true => [expected[0] / 4, expected[1] / 4, expected[2] / 4],
+            false => [255, 0, 0],
+        })
+        .collect()
+}
+
+fn assert_snapshot(name: &str, canvas: &Canvas) {
+    let rgb = canvas.to_rgb();
+    let approved = snapshot_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        write_png(&approved, canvas.size, &rgb);
+        return;
+    }
+    let Some((size, expected)) = read_png(&approved) else {
+        panic!(
+            "no approved snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            approved.display()
+        );
+    };
+    if size == canvas.size && expected == rgb {
+        return;
+    }
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let actual = out.join(format!("{name}.actual.png"));
+    write_png(&actual, canvas.size, &rgb);
+    let mut message = format!(
+        "{name} differs from {}, got {}",
+        approved.display(),
+        actual.display()
+    );
+    if size == canvas.size {
+        let diff_path = out.join(format!("{name}.diff.png"));
+        write_png(&diff_path, size, &diff(&expected, &rgb));
+        message += &format!(", diff {}", diff_path.display());
+    } else {
+        message += &format!(", size {size} instead of {}", canvas.size);
+    }
+    panic!("{message}");
+}
+
+fn question() -> Question {
+    Question::from_payload("q1|Which planet is known as the red planet?|Venus|Mars|Jupiter|Saturn")
+        .unwrap()
+}
+
+#[test]
+fn question_portrait() {
+    let question = question();
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_question(&question.text, None, 0);
+        renderer.draw_options(&question.options, 1, 0);
+    });
+    assert_snapshot("question_portrait", &canvas);
+}
+
+#[test]
+fn question_landscape() {
+    let question = question();
+    let canvas = render(LANDSCAPE, &DARK, |renderer| {
+        renderer.draw_question(&question.text, None, 0);
+        renderer.draw_options(&question.options, 0, 0);
+    });
+    assert_snapshot("question_landscape", &canvas);
+}
+
+#[test]
+fn question_with_progress() {
+    let question =
+        Question::from_payload("q3;3/10;Geography|Capital of Australia?|Sydney|Canberra|Perth")
+            .unwrap();
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_question(&question.text, question.progress.as_ref(), 0);
+        renderer.draw_options(&question.options, 2, 0);
+    });
+    assert_snapshot("question_with_progress", &canvas);
+}
+
+#[test]
+fn polish_diacritics() {
+    let question =
+        Question::from_payload("q4|Zażółć gęślą jaźń? ŁÓDŹ, ŚLĄSK|Zażółć|gęślą|jaźń|Łódź").unwrap();
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_question(&question.text, None, 0);
+        renderer.draw_options(&question.options, 1, 0);
+    });
+    assert_snapshot("polish_diacritics", &canvas);
+}
+
+#[test]
+fn result() {
+    let question = question();
+    let result = AnswerResult::from_payload("device|q1|1|-5").unwrap();
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_result(&question.options, 2, result.correct, result.points, 0);
+    });
+    assert_snapshot("result", &canvas);
+}
+
+#[test]
+fn text() {
+    let canvas = render(LANDSCAPE, &DARK, |renderer| {
+        renderer.draw_text("Next round starts in five minutes, grab a snack!", 0);
+        assert_eq!(renderer.page_count(), 1);
+    });
+    assert_snapshot("text", &canvas);
+}
+
+#[test]
+fn text_last_page() {
+    let text = "Teams, which answered all questions of the first round correctly, \
+        get a bonus point for every minute left on the clock. \
+        The second round starts right after the break, so stay around and keep your devices charged. \
+        Questions are shown on the device, answers are locked in with the enter button. \
+        Results of both rounds are announced at the end, together with the winners.";
+    let mut renderer = Renderer::new(Canvas::new(LANDSCAPE));
+    renderer.clear();
+    renderer.draw_text(text, 0);
+    let pages = renderer.page_count();
+    assert!(pages > 1, "{pages} pages");
+    let canvas = render(LANDSCAPE, &DARK, |renderer| {
+        renderer.draw_text(text, pages - 1);
+        assert_eq!(renderer.page_count(), pages);
+    });
+    assert_snapshot("text_last_page", &canvas);
+}
+
+#[test]
+fn markup() {
+    let markup = Markup::parse(
+        "^*Round 2*\\n[green]:check: Team A[/] 12\\n[red]:cross: Team B[/] 9\\n:pizza: at 12:30:00",
+    );
+    let canvas = render(PORTRAIT, &DARK, |renderer| renderer.draw_markup(&markup, 0));
+    assert_snapshot("markup", &canvas);
+}
+
+#[test]
+fn qr_code() {
+    let code = qr::encode("https://example.com/quiz", PORTRAIT).unwrap();
+    let canvas = render(PORTRAIT, &DARK, |renderer| renderer.draw_qr_code(&code));
+    assert_snapshot("qr_code", &canvas);
+}
+
+#[test]
+fn image() {
+    let data = include_bytes!("../src/logo.bmp");
+    let canvas = render(LANDSCAPE, &DARK, |renderer| renderer.draw_image(data));
+    assert_snapshot("image", &canvas);
+}
+
+#[test]
+fn idle_portrait() {
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_idle("quiz-3f2a", Some((12, 30)));
+    });
+    assert_snapshot("idle_portrait", &canvas);
+}
+
+#[test]
+fn idle_landscape() {
+    let canvas = render(LANDSCAPE, &DARK, |renderer| {
+        renderer.draw_idle("quiz-3f2a", None);
+    });
+    assert_snapshot("idle_landscape", &canvas);
+}
+
+#[test]
+fn diagnostics() {
+    let mut diagnostics =
+        NetworkDiagnostics::new("QuizNet", String::from("mqtt://quiz.local:1883"));
+    diagnostics.mqtt_state = MqttState::Connected;
+    diagnostics.rssi = Some(-60);
+    diagnostics.ip = Some(Ipv4Addr::new(192, 168, 1, 42));
+    diagnostics.gateway = Some(Ipv4Addr::new(192, 168, 1, 1));
+    diagnostics.uptime = Duration::from_secs(3725);
+    diagnostics.free_heap = 123_456;
+    let canvas = render(PORTRAIT, &DARK, |renderer| {
+        renderer.draw_diagnostics(&diagnostics);
+    });
+    assert_snapshot("diagnostics", &canvas);
+}
+
+#[test]
+fn light_theme() {
+    let question = question();
+    let canvas = render(PORTRAIT, &LIGHT, |renderer| {
+        renderer.draw_question(&question.text, None, 0);
+        renderer.draw_options(&question.options, 1, 0);
+    });
+    assert_snapshot("light_theme", &canvas);
+}
+
+#[test]
+fn high_contrast_theme() {
+    let question = question();
+    let result = AnswerResult::from_payload("device|q1|1|10").unwrap();
+    let canvas = render(PORTRAIT, &HIGH_CONTRAST, |renderer| {
+        renderer.draw_result(&question.options, 1, result.correct, result.points, 0);
+    });
+    assert_snapshot("high_contrast_theme", &canvas);
+}
+
+#[test]
+fn low_battery_and_disconnected() {
+    let mut renderer = Renderer::new(Canvas::new(PORTRAIT));
+    renderer.clear();
+    renderer.draw_status_bar(&StatusBarState {
+        battery_level: Some(5),
+        rssi: None,
+        mqtt_state: MqttState::Disconnected,
+        time: None,
+    });
+    renderer.draw_text("Offline", 0);
+    assert_snapshot("low_battery_and_disconnected", renderer.target_mut());
+}
+
+#[test]
+fn theme_switch() {
+    let mut renderer = Renderer::new(Canvas::new(PORTRAIT));
+    renderer.clear();
+    renderer.draw_status_bar(&STATUS);
+    renderer.set_theme(&LIGHT);
+    renderer.clear();
+    renderer.draw_status_bar(&STATUS);
+    renderer.draw_text("Light theme", 0);
+    assert_snapshot("theme_switch", renderer.target_mut());
+}
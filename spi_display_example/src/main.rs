@@ -23,6 +23,7 @@ use tinybmp::Bmp;
 
 use mipidsi::options::ColorInversion;
 
+// TTGO T-Display only, display profiles for other panels live in `mqtt_example`
 const DISPLAY_OFFSET: (u16, u16) = (52, 40);
 const DISPLAY_SIZE: (u16, u16) = (135, 240);
 